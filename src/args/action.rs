@@ -1,7 +1,29 @@
-use crate::args::{import::Import, serve::Serve};
+use crate::{
+    args::{import::Import, serve::Serve},
+    error::Result,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Action {
-    Serve(Serve),
+    Serve(Box<Serve>),
     Import(Import),
+    /// Server of the project config which failed to load.
+    Invalid {
+        label: String,
+        error: String,
+    },
+}
+
+impl Action {
+    /// Creates the action of the project config server with the given
+    /// label.
+    pub fn from_project((label, serve): (String, Result<Serve>)) -> Self {
+        match serve {
+            Ok(serve) => Action::Serve(Box::new(serve)),
+            Err(e) => Action::Invalid {
+                label,
+                error: e.to_string(),
+            },
+        }
+    }
 }
@@ -1,8 +1,13 @@
+use std::path::PathBuf;
+
 use pareg::Pareg;
 use termal::printcln;
 
 use crate::{
-    args::{action::Action, import::Import, serve::Serve},
+    args::{
+        action::Action, import::Import, next_arg, project::Project,
+        serve::Serve,
+    },
     error::Result,
 };
 
@@ -19,13 +24,32 @@ impl Args {
 
     pub fn parse(mut args: Pareg) -> Result<Args> {
         let mut parsed = Self::default();
+        if args.peek().is_none() {
+            match Project::find() {
+                Some(file) => {
+                    parsed.actions = Project::load(file)?
+                        .into_iter()
+                        .map(Action::from_project)
+                        .collect();
+                }
+                None => Self::help(),
+            }
+            return Ok(parsed);
+        }
 
         while let Some(arg) = args.peek() {
             match arg {
                 "s" | "serve" => {
                     args.next();
                     let serve = Serve::parse(&mut args)?;
-                    parsed.actions.push(Action::Serve(serve));
+                    parsed.actions.push(Action::Serve(Box::new(serve)));
+                }
+                "--config" => {
+                    let file: PathBuf = next_arg(&mut args)?;
+                    let servers = Project::load(file)?;
+                    parsed
+                        .actions
+                        .extend(servers.into_iter().map(Action::from_project));
                 }
                 "i" | "import" => {
                     args.next();
@@ -56,6 +80,9 @@ Blazingly fast API Mock Server written in Rust.
 
 {'g}Usage{'_}:
   {'c}faupi{'_} [{'y}flags{'_}] [{'db}action{'_}]
+  {'c}faupi{'_}
+    Starts all the servers of the project config ({'y}faupi.toml{'_} or
+    {'y}faupi.yaml{'_}) in the current directory.
 
 {'g}Flags{'_}:
   {'y}-h  --help{'_}
@@ -64,13 +91,17 @@ Blazingly fast API Mock Server written in Rust.
   {'y}-v  --version{'_}
     Displays the version number of {'c}faupi{'_}.
 
+  {'y}--config{'_} <filepath>
+    Starts all the servers of the given project config.
+
 {'g}Actions{'_}:
   {'db}s  serve{'_} {'bl}[serve arguments] [--]{'_}
     Creates the API mock server based on the arguments.
 
 {'g}Serve arguments{'_}:
-  {'y}-s  --spec{'_} <filepath>
-    Path to the specification file.
+  {'y}-s  --spec{'_} <path>
+    Path to the specification file, directory or glob. Can be used multiple
+    times, the specifications are merged.
 
   {'y}-a  --address{'_} <address>
     Mock API server address.
@@ -78,8 +109,25 @@ Blazingly fast API Mock Server written in Rust.
   {'y}-p  --port{'_} <port>
     Mock API server port.
 
+  {'y}-d  --delay{'_} <ms|min..max>
+    Default response delay, used when the response doesn't set its own.
+
   {'y}-c  --cors{'_}
-    Enables CORS support.",
+    Enables CORS support for any origin, unless the specification defines
+    CORS policy.
+
+  {'y}--tls-cert{'_} <filepath>  {'y}--tls-key{'_} <filepath>
+    Serves HTTPS using the given PEM certificate chain and private key.
+
+  {'y}--tls-self-signed{'_}
+    Serves HTTPS using a self-signed certificate generated on startup.
+
+  {'y}--profile{'_} <name>
+    Applies the overrides of the given profile of the specification files.
+
+  {'y}--proxy{'_} <url>
+    Forwards the requests not matching any endpoint to the given HTTP
+    upstream.",
             termal::gradient("Martan03", (0, 220, 255), (175, 80, 255)),
             Self::VERSION_NUMBER
         );
@@ -5,6 +5,7 @@ use crate::error::{Error, Result};
 pub mod action;
 pub mod args_struct;
 pub mod import;
+pub mod project;
 pub mod serve;
 
 fn missing_param_err(param: &str) -> Error {
@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    args::serve::{DEFAULT_ADDRESS, DEFAULT_PORT, Serve},
+    error::{Error, Result},
+    specs::delay::Delay,
+};
+
+/// Project config files looked up in the current directory when `faupi` is
+/// run without arguments.
+pub const PROJECT_FILES: &[&str] = &["faupi.toml", "faupi.yaml", "faupi.yml"];
+
+/// Project config listing the mock servers to start. The servers are parsed
+/// one by one, so that an invalid server doesn't prevent starting the others.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    pub servers: Vec<serde_yaml::Value>,
+}
+
+/// Mock server of the project, the fields match the serve arguments.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Name of the server used in the error messages.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Specification files, directories or globs, relative to the project
+    /// config.
+    pub specs: Vec<PathBuf>,
+    #[serde(default)]
+    pub cors: bool,
+    #[serde(default)]
+    pub delay: Option<Delay>,
+    #[serde(default)]
+    pub tls: Option<TlsSettings>,
+    /// URL of the upstream of the requests not matching any endpoint.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Selected profile of the specification files.
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// TLS settings of the server, either the certificate files or the
+/// self-signed certificate.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+    #[serde(default)]
+    pub self_signed: bool,
+}
+
+impl Project {
+    /// Finds the project config file in the current directory.
+    pub fn find() -> Option<PathBuf> {
+        PROJECT_FILES
+            .iter()
+            .map(PathBuf::from)
+            .find(|file| file.is_file())
+    }
+
+    /// Loads the servers from the project config file based on the file
+    /// extension. Each server is returned with its label and the error of
+    /// its config, if any.
+    /// # Supported extensions:
+    /// - `.toml`
+    /// - `.yaml`, `.yml`
+    pub fn load(
+        file: impl AsRef<Path>,
+    ) -> Result<Vec<(String, Result<Serve>)>> {
+        let file = file.as_ref();
+        let content = fs::read_to_string(file).map_err(|e| {
+            Error::Msg(format!("Loading '{}': {e}", file.display()))
+        })?;
+        let ext = file.extension().and_then(|s| s.to_str());
+        let project: Project = match ext {
+            Some("toml") => toml::from_str(&content)?,
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
+            _ => {
+                return Err(Error::Msg(
+                    "Unsupported project file type".into(),
+                ));
+            }
+        };
+
+        let dir = file.parent().unwrap_or(Path::new("."));
+        Ok(project
+            .servers
+            .into_iter()
+            .map(|server| {
+                let label = label(&server);
+                let serve = serde_yaml::from_value(server)
+                    .map_err(Error::from)
+                    .and_then(|config| Serve::from_config(config, dir));
+                (label, serve)
+            })
+            .collect())
+    }
+}
+
+/// Gets the server name, or its address when it has no name.
+fn label(server: &serde_yaml::Value) -> String {
+    if let Some(name) = server.get("name").and_then(|n| n.as_str()) {
+        return name.to_owned();
+    }
+    let address = server.get("address").and_then(|a| a.as_str());
+    let port = server.get("port").and_then(|p| p.as_u64());
+    format!(
+        "{}:{}",
+        address.unwrap_or(DEFAULT_ADDRESS),
+        port.unwrap_or(DEFAULT_PORT.into())
+    )
+}
@@ -1,33 +1,65 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use pareg::Pareg;
 use tokio::sync::RwLock;
 
 use crate::{
-    args::{missing_param_err, next_arg},
+    args::{missing_param_err, next_arg, project::ServerConfig},
     error::{Error, Result},
-    server::{router::Router, server_struct::Server},
-    specs::{mock_config::MockConfig, watch_specs},
+    server::{
+        proxy::Proxy, router::Router, server_struct::Server, tls::TlsConfig,
+    },
+    specs::{
+        cors::Cors,
+        delay::Delay,
+        sources::{LoadedSpecs, Source},
+        watch_specs,
+    },
 };
 
+/// Address of the server when not set.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1";
+/// Port of the server when not set.
+pub const DEFAULT_PORT: u16 = 3000;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Serve {
-    // Specification file path
-    pub file: PathBuf,
+    // Name of the server from the project config
+    pub name: Option<String>,
+    // Specification files, directories or globs
+    pub specs: Vec<PathBuf>,
     // API mock server address
     pub server: String,
     // API mock server port
     pub port: u16,
-    // Whether allow CORS
+    // Whether allow CORS from any origin when the specification doesn't
+    // define CORS policy
     pub cors: bool,
+    // Default response delay
+    pub delay: Option<Delay>,
+    // TLS certificate source
+    pub tls: Option<TlsConfig>,
+    // Upstream of the requests not matching any endpoint
+    pub proxy: Option<Proxy>,
+    // Selected profile of the specification files
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Default)]
 struct ServeParser {
-    file: Option<PathBuf>,
+    specs: Vec<PathBuf>,
     server: Option<String>,
     port: Option<u16>,
     cors: Option<bool>,
+    delay: Option<Delay>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    tls_self_signed: bool,
+    proxy: Option<Proxy>,
+    profile: Option<String>,
 }
 
 impl Serve {
@@ -35,9 +67,18 @@ impl Serve {
         let mut parsed = ServeParser::default();
         while let Some(arg) = args.peek() {
             match arg {
-                "-s" | "--spec" => parsed.file = Some(next_arg(args)?),
+                "-s" | "--spec" => parsed.specs.push(next_arg(args)?),
                 "-a" | "--address" => parsed.server = Some(next_arg(args)?),
                 "-p" | "--port" => parsed.port = Some(next_arg(args)?),
+                "-d" | "--delay" => parsed.delay = Some(next_arg(args)?),
+                "--tls-cert" => parsed.tls_cert = Some(next_arg(args)?),
+                "--tls-key" => parsed.tls_key = Some(next_arg(args)?),
+                "--proxy" => parsed.proxy = Some(next_arg(args)?),
+                "--profile" => parsed.profile = Some(next_arg(args)?),
+                "--tls-self-signed" => {
+                    args.next();
+                    parsed.tls_self_signed = true
+                }
                 "-c" | "--cors" => {
                     args.next();
                     parsed.cors = Some(true)
@@ -52,14 +93,60 @@ impl Serve {
         Serve::try_from(parsed)
     }
 
+    /// Creates the serve action from the project server config. The paths
+    /// are relative to the project config directory `dir`.
+    pub fn from_config(config: ServerConfig, dir: &Path) -> Result<Serve> {
+        let tls = config.tls.unwrap_or_default();
+        let proxy = config.proxy.map(|p| p.parse::<Proxy>()).transpose()?;
+        let parsed = ServeParser {
+            specs: config.specs.iter().map(|s| dir.join(s)).collect(),
+            server: config.address,
+            port: config.port,
+            cors: Some(config.cors),
+            delay: config.delay,
+            tls_cert: tls.cert.map(|c| dir.join(c)),
+            tls_key: tls.key.map(|k| dir.join(k)),
+            tls_self_signed: tls.self_signed,
+            proxy,
+            profile: config.profile,
+        };
+        let serve = Serve::try_from(parsed)?;
+        Ok(Serve {
+            name: config.name,
+            ..serve
+        })
+    }
+
+    /// Gets the server name, or its address when it has no name.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{}:{}", self.server, self.port),
+        }
+    }
+
     pub async fn run(&self) -> Result<()> {
-        let specs = MockConfig::load(&self.file)?;
-        let router = Arc::new(RwLock::new(Router::new(specs)?));
+        let sources = self
+            .specs
+            .iter()
+            .map(Source::new)
+            .collect::<Result<Vec<_>>>()?;
+        let profile = self.profile.as_deref();
+        let mut specs = LoadedSpecs::load(&sources, profile)?;
+        let config = std::mem::take(&mut specs.config);
+        let router = Arc::new(RwLock::new(Router::new(config)?));
 
-        let _watcher = watch_specs(&self.file, router.clone())?;
+        watch_specs(sources, self.profile.clone(), specs, router.clone())?;
 
-        let server =
-            Server::new((&self.server, self.port), router, self.cors).await?;
+        let server = Server::new(
+            (&self.server, self.port),
+            router,
+            self.cors.then(Cors::default),
+            self.delay.clone(),
+            self.proxy.clone(),
+            self.tls.as_ref(),
+        )
+        .await?;
         server.run().await
     }
 }
@@ -68,11 +155,30 @@ impl TryFrom<ServeParser> for Serve {
     type Error = Error;
 
     fn try_from(value: ServeParser) -> Result<Self> {
+        let tls = match (value.tls_cert, value.tls_key) {
+            (Some(cert), Some(key)) => Some(TlsConfig::Files { cert, key }),
+            (Some(_), None) => return Err(missing_param_err("--tls-key")),
+            (None, Some(_)) => return Err(missing_param_err("--tls-cert")),
+            (None, None) if value.tls_self_signed => {
+                Some(TlsConfig::SelfSigned)
+            }
+            (None, None) => None,
+        };
+
+        if value.specs.is_empty() {
+            return Err(missing_param_err("--spec"));
+        }
+
         Ok(Serve {
-            file: value.file.ok_or_else(|| missing_param_err("--spec"))?,
-            server: value.server.unwrap_or("127.0.0.1".into()),
-            port: value.port.unwrap_or(3000),
+            name: None,
+            specs: value.specs,
+            server: value.server.unwrap_or(DEFAULT_ADDRESS.into()),
+            port: value.port.unwrap_or(DEFAULT_PORT),
             cors: value.cors.unwrap_or_default(),
+            delay: value.delay,
+            tls,
+            proxy: value.proxy,
+            profile: value.profile,
         })
     }
 }
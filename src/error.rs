@@ -17,6 +17,8 @@ pub enum Error {
     #[error(transparent)]
     HyperHttp(#[from] hyper::http::Error),
     #[error(transparent)]
+    HyperClient(#[from] hyper_util::client::legacy::Error),
+    #[error(transparent)]
     Notify(#[from] notify::Error),
     #[error(transparent)]
     Pareg(#[from] pareg::ArgError),
@@ -34,6 +36,14 @@ pub enum Error {
     RefOas3(#[from] oas3::spec::RefError),
     #[error(transparent)]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error(transparent)]
+    Tls(#[from] rustls::Error),
+    #[error(transparent)]
+    Pem(#[from] rustls_pki_types::pem::Error),
+    #[error(transparent)]
+    Rcgen(#[from] rcgen::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
     #[error("{0}")]
     Msg(String),
 }
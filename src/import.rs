@@ -10,6 +10,7 @@ use crate::{
         body::{Mapping, body::Body, type_constraint::TypeConstraint},
         method::Method,
         mock_config::MockConfig,
+        request::RequestBody,
         response::{EndpointResponse, Response},
         spec::Spec,
         status_code::StatusCode,
@@ -31,8 +32,15 @@ impl Import {
                 let spec = Spec {
                     method: Method::try_from(method)?,
                     url: url.clone(),
-                    request: req,
-                    response: EndpointResponse::Single(res),
+                    request: req.map(|b| RequestBody::Body(b).into()),
+                    response: EndpointResponse::Single(Box::new(res)),
+                    on_invalid: None,
+                    rate_limit: None,
+                    auth: None,
+                    websocket: None,
+                    graphql: None,
+                    grpc: None,
+                    mount: None,
                 };
                 specs.specs.push(spec);
             }
@@ -74,8 +82,8 @@ impl Import {
 
         Ok(Response {
             status: StatusCode::try_from(status.as_str())?,
-            delay: None,
             body,
+            ..Default::default()
         })
     }
 
@@ -85,7 +93,7 @@ impl Import {
         is_req: bool,
     ) -> Result<Body> {
         let ty = match &obj.schema_type {
-            Some(SchemaTypeSet::Single(ty)) => ty.clone(),
+            Some(SchemaTypeSet::Single(ty)) => *ty,
             Some(SchemaTypeSet::Multiple(items)) => {
                 items.first().copied().unwrap_or(SchemaType::Null)
             }
@@ -96,27 +104,29 @@ impl Import {
             SchemaType::Array => Self::parse_array(obj, spec, is_req),
             SchemaType::Object => Self::parse_object(obj, spec, is_req),
             SchemaType::String => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("string", None))
+                Body::Constraint(Box::new(TypeConstraint::new("string", None)))
             } else {
                 Body::String(String::new())
             }),
             SchemaType::Integer => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("number", None))
+                Body::Constraint(Box::new(TypeConstraint::new("number", None)))
             } else {
                 Body::Number(serde_yaml::Number::from(0))
             }),
             SchemaType::Number => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("number", None))
+                Body::Constraint(Box::new(TypeConstraint::new("number", None)))
             } else {
                 Body::Number(serde_yaml::Number::from(0.0))
             }),
             SchemaType::Boolean => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("boolean", None))
+                Body::Constraint(Box::new(TypeConstraint::new(
+                    "boolean", None,
+                )))
             } else {
                 Body::Bool(true)
             }),
             SchemaType::Null => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("any", None))
+                Body::Constraint(Box::new(TypeConstraint::new("any", None)))
             } else {
                 Body::Null
             }),
@@ -130,7 +140,9 @@ impl Import {
     ) -> Result<Body> {
         match schema {
             Schema::Boolean(b) => Ok(if is_req {
-                Body::Constraint(TypeConstraint::new("boolean", None))
+                Body::Constraint(Box::new(TypeConstraint::new(
+                    "boolean", None,
+                )))
             } else {
                 Body::Bool(b.0)
             }),
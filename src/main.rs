@@ -6,7 +6,7 @@ use tokio::task::JoinSet;
 
 use crate::{
     args::{action::Action, args_struct::Args},
-    error::Result,
+    error::{Error, Result},
 };
 
 pub mod args;
@@ -44,16 +44,34 @@ fn start_logger() -> Result<LoggerHandle> {
 async fn run() -> Result<()> {
     let args = Args::parse(Pareg::args())?;
     let mut set = JoinSet::new();
+    let mut invalid = 0;
     for action in args.actions {
         match action {
-            Action::Serve(s) => _ = set.spawn(async move { s.run().await }),
+            Action::Serve(s) => {
+                _ = set.spawn(async move { (s.label(), s.run().await) })
+            }
             Action::Import(import) => import.run()?,
+            Action::Invalid { label, error } => {
+                eprintcln!("{'r}Error{'_} [{label}]: {error}");
+                invalid += 1;
+            }
         }
     }
 
+    // Servers fail independently, so that one failing server doesn't stop
+    // the others.
+    let total = set.len() + invalid;
+    let mut failed = invalid;
     while let Some(res) = set.join_next().await {
-        res??;
+        let (label, res) = res?;
+        if let Err(e) = res {
+            eprintcln!("{'r}Error{'_} [{label}]: {e}");
+            failed += 1;
+        }
     }
 
+    if failed > 0 {
+        return Err(Error::Msg(format!("{failed} of {total} servers failed")));
+    }
     Ok(())
 }
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use hyper::{Method, StatusCode, Uri, body::Bytes};
+use serde::Serialize;
+
+use crate::server::{
+    HyperRes, endpoint::Endpoint, full_body, journal::Journal,
+    query::parse_query, router::Router,
+};
+
+/// URL prefix of the faupi admin API.
+pub const ADMIN_PREFIX: &str = "/__faupi";
+
+/// Handles the faupi admin API requests.
+///
+/// # Endpoints:
+/// - `PUT /__faupi/pin?method=<method>&path=<path>&response=<name|index>` -
+///   pins the endpoint to the given response until reset.
+/// - `DELETE /__faupi/pin?method=<method>&path=<path>` - resets the pinned
+///   response of the endpoint.
+/// - `GET /__faupi/journal` - lists the recorded requests and messages.
+/// - `DELETE /__faupi/journal` - clears the journal.
+/// - `GET /__faupi/reload` - gets the result of the last specification
+///   reload.
+pub fn handle_admin(
+    method: &Method,
+    uri: &Uri,
+    router: &Router,
+    journal: &Journal,
+) -> HyperRes {
+    let path = &uri.path()[ADMIN_PREFIX.len()..];
+    let query = parse_query(uri.query());
+    match (method, path) {
+        (&Method::PUT, "/pin") => pin(&query, router),
+        (&Method::DELETE, "/pin") => unpin(&query, router),
+        (&Method::GET, "/journal") => list_journal(journal),
+        (&Method::DELETE, "/journal") => {
+            journal.clear();
+            text_res(StatusCode::OK, "Journal cleared")
+        }
+        (&Method::GET, "/reload") => json_res(&router.reload),
+        _ => text_res(StatusCode::NOT_FOUND, "Unknown admin endpoint"),
+    }
+}
+
+fn pin(query: &HashMap<String, String>, router: &Router) -> HyperRes {
+    let Some(key) = query.get("response") else {
+        return text_res(StatusCode::BAD_REQUEST, "Missing `response` param");
+    };
+    let ep = match find_endpoint(query, router) {
+        Ok(ep) => ep,
+        Err((status, msg)) => return text_res(status, msg),
+    };
+
+    if !ep.response.pin(key) {
+        return text_res(
+            StatusCode::BAD_REQUEST,
+            format!("Endpoint has no response '{key}'"),
+        );
+    }
+    text_res(StatusCode::OK, format!("Response '{key}' pinned"))
+}
+
+fn unpin(query: &HashMap<String, String>, router: &Router) -> HyperRes {
+    match find_endpoint(query, router) {
+        Ok(ep) => {
+            ep.response.unpin();
+            text_res(StatusCode::OK, "Pinned response reset")
+        }
+        Err((status, msg)) => text_res(status, msg),
+    }
+}
+
+fn list_journal(journal: &Journal) -> HyperRes {
+    json_res(&journal.entries())
+}
+
+fn json_res(value: &impl Serialize) -> HyperRes {
+    let body = serde_json::to_string(value).unwrap_or_default();
+    hyper::Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/json")
+        .body(full_body(body))
+        .unwrap()
+}
+
+/// Finds the endpoint given by the `method` and `path` query parameters.
+fn find_endpoint<'a>(
+    query: &HashMap<String, String>,
+    router: &'a Router,
+) -> std::result::Result<&'a Endpoint, (StatusCode, &'static str)> {
+    let (Some(method), Some(path)) = (query.get("method"), query.get("path"))
+    else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Missing `method` or `path` param",
+        ));
+    };
+    let Ok(method) = Method::from_bytes(method.to_uppercase().as_bytes())
+    else {
+        return Err((StatusCode::BAD_REQUEST, "Invalid method"));
+    };
+
+    router
+        .find(&method, path, &mut HashMap::new())
+        .ok_or((StatusCode::NOT_FOUND, "Endpoint not found"))
+}
+
+fn text_res(status: StatusCode, msg: impl Into<Bytes>) -> HyperRes {
+    hyper::Response::builder()
+        .status(status)
+        .body(full_body(msg))
+        .unwrap()
+}
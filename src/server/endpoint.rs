@@ -1,9 +1,23 @@
-use crate::specs::{body::body::Body, response::EndpointResponse};
+use crate::specs::{
+    auth::Auth,
+    graphql::GraphQl,
+    grpc::Grpc,
+    rate_limit::RateLimit,
+    request::Request,
+    response::{EndpointResponse, Response},
+    websocket::WebSocket,
+};
 
 #[derive(Debug)]
 pub struct Endpoint {
-    pub request: Option<Body>,
+    pub request: Option<Request>,
     pub response: EndpointResponse,
+    pub on_invalid: Option<Response>,
+    pub rate_limit: Option<RateLimit>,
+    pub auth: Option<Auth>,
+    pub websocket: Option<WebSocket>,
+    pub graphql: Option<GraphQl>,
+    pub grpc: Option<Grpc>,
 }
 
 impl Endpoint {
@@ -12,15 +26,75 @@ impl Endpoint {
         Self {
             request: None,
             response,
+            on_invalid: None,
+            rate_limit: None,
+            auth: None,
+            websocket: None,
+            graphql: None,
+            grpc: None,
         }
     }
 
     /// Sets the endpoint's request template to given value.
     pub fn request<T>(mut self, request: T) -> Self
     where
-        T: Into<Option<Body>>,
+        T: Into<Option<Request>>,
     {
         self.request = request.into();
         self
     }
+
+    /// Sets the endpoint's response to invalid requests to given value.
+    pub fn on_invalid<T>(mut self, on_invalid: T) -> Self
+    where
+        T: Into<Option<Response>>,
+    {
+        self.on_invalid = on_invalid.into();
+        self
+    }
+
+    /// Sets the endpoint's rate limit to given value.
+    pub fn rate_limit<T>(mut self, rate_limit: T) -> Self
+    where
+        T: Into<Option<RateLimit>>,
+    {
+        self.rate_limit = rate_limit.into();
+        self
+    }
+
+    /// Sets the endpoint's authentication to given value.
+    pub fn auth<T>(mut self, auth: T) -> Self
+    where
+        T: Into<Option<Auth>>,
+    {
+        self.auth = auth.into();
+        self
+    }
+
+    /// Sets the endpoint's WebSocket specification to given value.
+    pub fn websocket<T>(mut self, websocket: T) -> Self
+    where
+        T: Into<Option<WebSocket>>,
+    {
+        self.websocket = websocket.into();
+        self
+    }
+
+    /// Sets the endpoint's GraphQL specification to given value.
+    pub fn graphql<T>(mut self, graphql: T) -> Self
+    where
+        T: Into<Option<GraphQl>>,
+    {
+        self.graphql = graphql.into();
+        self
+    }
+
+    /// Sets the endpoint's gRPC specification to given value.
+    pub fn grpc<T>(mut self, grpc: T) -> Self
+    where
+        T: Into<Option<Grpc>>,
+    {
+        self.grpc = grpc.into();
+        self
+    }
 }
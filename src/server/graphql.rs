@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+
+use graphql_parser::query::{
+    self, Definition, Document, OperationDefinition, Selection, SelectionSet,
+};
+use http_body_util::BodyExt;
+use hyper::{Method, Request, StatusCode, body::Incoming};
+use serde::Deserialize;
+
+use crate::{
+    server::{HyperRes, full_body, query::parse_query, url::var::UrlVar},
+    specs::{
+        body::body::Body,
+        graphql::{GraphQl, Schema, schema::Fragments},
+    },
+};
+
+/// Maximum nesting of the selection sets, fragments and values in the query.
+const MAX_DEPTH: usize = 32;
+
+/// GraphQL request sent by the client.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQlRequest {
+    query: String,
+    #[serde(default)]
+    operation_name: Option<String>,
+    #[serde(default)]
+    variables: Option<serde_json::Value>,
+}
+
+/// Handles the GraphQL request, `POST` with JSON body or `GET` with query
+/// parameters.
+///
+/// The request variables are added to the `vars`, so they can be used in the
+/// mocked `data` and `errors`. When no mock matches the operation, its result
+/// is faked based on the schema.
+pub async fn handle(
+    req: Request<Incoming>,
+    spec: &GraphQl,
+    mut vars: HashMap<String, UrlVar>,
+    templates: &HashMap<String, Body>,
+) -> HyperRes {
+    let gql = match read_request(req).await {
+        Ok(gql) => gql,
+        Err(msg) => return error_res(StatusCode::BAD_REQUEST, &msg),
+    };
+    // The parser is recursive, so the nesting is checked before parsing.
+    if nesting(&gql.query) > MAX_DEPTH {
+        return error_res(StatusCode::BAD_REQUEST, "Query is nested too deep");
+    }
+    let doc = match query::parse_query::<String>(&gql.query) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let msg = format!("Invalid query: {e}");
+            return error_res(StatusCode::BAD_REQUEST, &msg);
+        }
+    };
+
+    let fragments: Fragments = doc
+        .definitions
+        .iter()
+        .filter_map(|def| match def {
+            Definition::Fragment(frag) => Some((frag.name.as_str(), frag)),
+            _ => None,
+        })
+        .collect();
+    let Some((name, root, set)) =
+        find_operation(&doc, gql.operation_name.as_deref())
+    else {
+        return error_res(StatusCode::BAD_REQUEST, "Unknown operation");
+    };
+    if let Err(msg) = check_selection(set, &fragments, &mut HashMap::new(), 0)
+    {
+        return error_res(StatusCode::BAD_REQUEST, &msg);
+    }
+
+    let variables = gql
+        .variables
+        .and_then(|v| serde_yaml::to_value(v).ok())
+        .unwrap_or_default();
+    if let serde_yaml::Value::Mapping(map) = &variables {
+        for (key, value) in map {
+            if let Some(key) = key.as_str() {
+                vars.insert(key.to_owned(), UrlVar::Value(value.clone()));
+            }
+        }
+    }
+
+    let fields = root_fields(set, &fragments, &mut HashSet::new());
+    let res = if let Some(op) =
+        spec.find(name, &fields, &variables, &vars, templates)
+    {
+        op.resolve(&vars, templates)
+    } else if let Some(schema) = &spec.sdl {
+        let mut res = serde_yaml::Mapping::new();
+        let root = root_type(schema, root);
+        res.insert("data".into(), schema.fake(root, set, &fragments));
+        serde_yaml::Value::Mapping(res)
+    } else {
+        return error_res(StatusCode::OK, "No mock matches the operation");
+    };
+    json_res(
+        StatusCode::OK,
+        serde_json::to_string(&res).unwrap_or_default(),
+    )
+}
+
+/// Reads GraphQL request from the JSON body or the query parameters.
+async fn read_request(
+    req: Request<Incoming>,
+) -> std::result::Result<GraphQlRequest, String> {
+    if req.method() == Method::GET {
+        let mut query = parse_query(req.uri().query());
+        let variables = match query.remove("variables") {
+            Some(vars) => Some(
+                serde_json::from_str(&vars)
+                    .map_err(|e| format!("Invalid variables: {e}"))?,
+            ),
+            None => None,
+        };
+        return Ok(GraphQlRequest {
+            query: query.remove("query").ok_or("Missing query")?,
+            operation_name: query.remove("operationName"),
+            variables,
+        });
+    }
+
+    let body = req
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| format!("Failed to read body: {e}"))?
+        .to_bytes();
+    serde_json::from_slice(&body)
+        .map_err(|e| format!("Invalid GraphQL request: {e}"))
+}
+
+/// Kind of the GraphQL operation.
+#[derive(Debug, Clone, Copy)]
+enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+/// Finds the operation with the given name, or the only operation when no
+/// name is given. Returns the operation name, kind and selection set.
+fn find_operation<'a>(
+    doc: &'a Document<'a, String>,
+    name: Option<&str>,
+) -> Option<(Option<&'a str>, OperationKind, &'a SelectionSet<'a, String>)> {
+    let mut ops = doc.definitions.iter().filter_map(|def| match def {
+        Definition::Operation(op) => Some(operation_parts(op)),
+        Definition::Fragment(_) => None,
+    });
+    match name {
+        Some(name) => ops.find(|(n, ..)| *n == Some(name)),
+        None => {
+            let op = ops.next();
+            ops.next().is_none().then_some(op).flatten()
+        }
+    }
+}
+
+fn operation_parts<'a>(
+    op: &'a OperationDefinition<'a, String>,
+) -> (Option<&'a str>, OperationKind, &'a SelectionSet<'a, String>) {
+    match op {
+        OperationDefinition::SelectionSet(set) => {
+            (None, OperationKind::Query, set)
+        }
+        OperationDefinition::Query(q) => {
+            (q.name.as_deref(), OperationKind::Query, &q.selection_set)
+        }
+        OperationDefinition::Mutation(m) => {
+            (m.name.as_deref(), OperationKind::Mutation, &m.selection_set)
+        }
+        OperationDefinition::Subscription(s) => (
+            s.name.as_deref(),
+            OperationKind::Subscription,
+            &s.selection_set,
+        ),
+    }
+}
+
+/// Gets names of the root fields selected by the operation. The `visited`
+/// fragments are the fragments being expanded, so that cyclic fragment isn't
+/// expanded again.
+fn root_fields<'a>(
+    set: &'a SelectionSet<'a, String>,
+    fragments: &Fragments<'a>,
+    visited: &mut HashSet<&'a str>,
+) -> Vec<&'a str> {
+    let mut fields = vec![];
+    for item in &set.items {
+        match item {
+            Selection::Field(field) => fields.push(field.name.as_str()),
+            Selection::FragmentSpread(spread) => {
+                let name = spread.fragment_name.as_str();
+                if let Some(frag) = fragments.get(name)
+                    && visited.insert(name)
+                {
+                    fields.extend(root_fields(
+                        &frag.selection_set,
+                        fragments,
+                        visited,
+                    ));
+                    visited.remove(name);
+                }
+            }
+            Selection::InlineFragment(frag) => {
+                fields.extend(root_fields(
+                    &frag.selection_set,
+                    fragments,
+                    visited,
+                ));
+            }
+        }
+    }
+    fields
+}
+
+/// Checks that no fragment spreads itself and the selection with the
+/// fragments expanded isn't nested deeper than [`MAX_DEPTH`]. The `depths`
+/// contain the nesting of the checked fragments, `None` for the fragments
+/// being checked.
+fn check_selection<'a>(
+    set: &'a SelectionSet<'a, String>,
+    fragments: &Fragments<'a>,
+    depths: &mut HashMap<&'a str, Option<usize>>,
+    depth: usize,
+) -> std::result::Result<usize, String> {
+    if depth > MAX_DEPTH {
+        return Err("Query is nested too deep".into());
+    }
+    let mut max = depth;
+    for item in &set.items {
+        let nested = match item {
+            Selection::Field(field) => check_selection(
+                &field.selection_set,
+                fragments,
+                depths,
+                depth + 1,
+            )?,
+            Selection::InlineFragment(frag) => {
+                check_selection(&frag.selection_set, fragments, depths, depth)?
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.fragment_name.as_str();
+                let Some(frag) = fragments.get(name) else {
+                    continue;
+                };
+                let frag_depth = match depths.get(name) {
+                    Some(Some(frag_depth)) => *frag_depth,
+                    Some(None) => {
+                        return Err(format!(
+                            "Fragment '{name}' spreads itself"
+                        ));
+                    }
+                    None => {
+                        depths.insert(name, None);
+                        let frag_depth = check_selection(
+                            &frag.selection_set,
+                            fragments,
+                            depths,
+                            0,
+                        )?;
+                        depths.insert(name, Some(frag_depth));
+                        frag_depth
+                    }
+                };
+                depth + frag_depth
+            }
+        };
+        if nested > MAX_DEPTH {
+            return Err("Query is nested too deep".into());
+        }
+        max = max.max(nested);
+    }
+    Ok(max)
+}
+
+/// Gets the maximum nesting of the brackets in the query, ignoring strings
+/// and comments.
+fn nesting(query: &str) -> usize {
+    let mut chars = query.chars().peekable();
+    let (mut depth, mut max) = (0usize, 0);
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '[' | '(' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            '}' | ']' | ')' => depth = depth.saturating_sub(1),
+            '#' => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '"' => {
+                let mut escaped = false;
+                chars.by_ref().find(|c| {
+                    let end = *c == '"' && !escaped;
+                    escaped = *c == '\\' && !escaped;
+                    end
+                });
+            }
+            _ => {}
+        }
+    }
+    max
+}
+
+fn root_type(schema: &Schema, kind: OperationKind) -> &str {
+    match kind {
+        OperationKind::Query => &schema.query,
+        OperationKind::Mutation => &schema.mutation,
+        OperationKind::Subscription => &schema.subscription,
+    }
+}
+
+/// Creates GraphQL response containing single error.
+fn error_res(status: StatusCode, msg: &str) -> HyperRes {
+    let body = serde_json::json!({ "errors": [{ "message": msg }] });
+    json_res(status, body.to_string())
+}
+
+fn json_res(status: StatusCode, body: String) -> HyperRes {
+    hyper::Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(full_body(body))
+        .unwrap()
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use futures_util::stream;
+use http_body_util::{BodyExt, StreamBody};
+use hyper::{
+    HeaderMap, Request, StatusCode,
+    body::{Bytes, Frame, Incoming},
+    header::{CONTENT_TYPE, HeaderValue},
+};
+use prost_reflect::{
+    DynamicMessage, MessageDescriptor, SerializeOptions, prost::Message,
+};
+use tokio::time::sleep;
+
+use crate::{
+    server::{HyperRes, full_body, url::var::UrlVar},
+    specs::{
+        body::body::Body, delay::Delay, grpc::Grpc, response::EndpointResponse,
+    },
+};
+
+/// gRPC status codes used by the mock.
+mod code {
+    pub const OK: u16 = 0;
+    pub const UNKNOWN: u16 = 2;
+    pub const INVALID_ARGUMENT: u16 = 3;
+    pub const DEADLINE_EXCEEDED: u16 = 4;
+    pub const NOT_FOUND: u16 = 5;
+    pub const ALREADY_EXISTS: u16 = 6;
+    pub const PERMISSION_DENIED: u16 = 7;
+    pub const RESOURCE_EXHAUSTED: u16 = 8;
+    pub const UNIMPLEMENTED: u16 = 12;
+    pub const INTERNAL: u16 = 13;
+    pub const UNAVAILABLE: u16 = 14;
+    pub const UNAUTHENTICATED: u16 = 16;
+}
+
+/// Handles the unary gRPC request.
+///
+/// Fields of the request message are added to the `vars`. The response is
+/// taken from the first matching case, or the endpoint `response`. Response
+/// with other than `200` status is sent as gRPC error with the corresponding
+/// status code.
+pub async fn handle(
+    req: Request<Incoming>,
+    spec: &Grpc,
+    response: &EndpointResponse,
+    hint: Option<&str>,
+    delay: Option<&Delay>,
+    mut vars: HashMap<String, UrlVar>,
+    templates: &HashMap<String, Body>,
+) -> HyperRes {
+    let is_grpc = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|c| c.to_str().ok())
+        .is_some_and(|c| c.starts_with("application/grpc"));
+    let Some(method) = spec.method.as_ref().filter(|_| is_grpc) else {
+        return hyper::Response::builder()
+            .status(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+            .body(full_body("Expected gRPC request"))
+            .unwrap();
+    };
+
+    let request = match read_message(req, method.input()).await {
+        Ok(request) => request,
+        Err((code, msg)) => return grpc_res(None, code, &msg),
+    };
+    if let serde_yaml::Value::Mapping(map) = &request {
+        for (key, value) in map {
+            if let Some(key) = key.as_str() {
+                vars.insert(key.to_owned(), UrlVar::Value(value.clone()));
+            }
+        }
+    }
+
+    let response = spec
+        .find(&request, &vars, templates)
+        .unwrap_or(response)
+        .get(hint);
+    if let Some(delay) = response.delay.as_ref().or(delay) {
+        sleep(delay.sample()).await;
+    }
+
+    let body = response.expand_vars(&vars, templates);
+    if !(200..300).contains(&response.status.0) {
+        let msg = match body {
+            serde_yaml::Value::String(msg) => msg,
+            serde_yaml::Value::Null => String::new(),
+            body => serde_json::to_string(&body).unwrap_or_default(),
+        };
+        return grpc_res(None, status_code(response.status.0), &msg);
+    }
+
+    let msg = serde_json::to_value(&body)
+        .map_err(|e| e.to_string())
+        .and_then(|body| {
+            DynamicMessage::deserialize(method.output(), body)
+                .map_err(|e| e.to_string())
+        });
+    match msg {
+        Ok(msg) => grpc_res(Some(msg.encode_to_vec()), code::OK, ""),
+        Err(e) => {
+            let msg = format!("Response doesn't match the message type: {e}");
+            grpc_res(None, code::INTERNAL, &msg)
+        }
+    }
+}
+
+/// Reads the length prefixed request message and converts it to value.
+async fn read_message(
+    req: Request<Incoming>,
+    desc: MessageDescriptor,
+) -> std::result::Result<serde_yaml::Value, (u16, String)> {
+    let body = req
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| (code::INTERNAL, format!("Failed to read body: {e}")))?
+        .to_bytes();
+
+    let invalid = || (code::INVALID_ARGUMENT, "Invalid message frame".into());
+    let (prefix, data) = body.split_at_checked(5).ok_or_else(invalid)?;
+    if prefix[0] != 0 {
+        return Err((code::UNIMPLEMENTED, "Compression not supported".into()));
+    }
+    let len = u32::from_be_bytes([prefix[1], prefix[2], prefix[3], prefix[4]]);
+    let data = data.get(..len as usize).ok_or_else(invalid)?;
+
+    let msg = DynamicMessage::decode(desc, data)
+        .map_err(|e| (code::INVALID_ARGUMENT, e.to_string()))?;
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false)
+        .skip_default_fields(false);
+    msg.serialize_with_options(serde_yaml::value::Serializer, &options)
+        .map_err(|e| (code::INTERNAL, e.to_string()))
+}
+
+/// Creates gRPC response with the optional message and the status trailers.
+fn grpc_res(msg: Option<Vec<u8>>, code: u16, message: &str) -> HyperRes {
+    let mut frames = vec![];
+    if let Some(msg) = msg {
+        let mut data = Vec::with_capacity(msg.len() + 5);
+        data.push(0);
+        data.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        data.extend(msg);
+        frames.push(Ok(Frame::data(Bytes::from(data))));
+    }
+
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(code));
+    if !message.is_empty() {
+        let message: String =
+            form_urlencoded::byte_serialize(message.as_bytes())
+                .collect::<String>()
+                .replace('+', "%20");
+        if let Ok(message) = HeaderValue::from_str(&message) {
+            trailers.insert("grpc-message", message);
+        }
+    }
+    frames.push(Ok(Frame::trailers(trailers)));
+
+    hyper::Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/grpc")
+        .body(StreamBody::new(stream::iter(frames)).boxed())
+        .unwrap()
+}
+
+/// Maps the HTTP status to the gRPC status code.
+fn status_code(status: u16) -> u16 {
+    match status {
+        400 => code::INVALID_ARGUMENT,
+        401 => code::UNAUTHENTICATED,
+        403 => code::PERMISSION_DENIED,
+        404 => code::NOT_FOUND,
+        409 => code::ALREADY_EXISTS,
+        429 => code::RESOURCE_EXHAUSTED,
+        501 => code::UNIMPLEMENTED,
+        503 => code::UNAVAILABLE,
+        504 => code::DEADLINE_EXCEEDED,
+        500..600 => code::INTERNAL,
+        _ => code::UNKNOWN,
+    }
+}
@@ -0,0 +1,234 @@
+use futures_util::stream;
+use hyper::{
+    HeaderMap,
+    body::Bytes,
+    header::{CONTENT_TYPE, COOKIE},
+};
+
+use crate::specs::validation::ValidationError;
+
+/// Kind of the incoming request body based on its `Content-Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Json,
+    Form,
+    Multipart,
+    Xml,
+    Text,
+}
+
+/// Incoming request body parsed according to its `Content-Type`.
+#[derive(Debug, Clone)]
+pub struct IncomingBody {
+    pub kind: BodyKind,
+    /// Value of the `Content-Type` header.
+    pub content_type: String,
+    pub raw: Bytes,
+    /// Parsed body, fields of forms are mapped to object fields.
+    pub value: serde_yaml::Value,
+}
+
+impl IncomingBody {
+    /// Parses the body according to the `Content-Type` in the `headers`.
+    pub async fn parse(
+        headers: &HeaderMap,
+        raw: Bytes,
+    ) -> Result<Self, ValidationError> {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        let kind = match BodyKind::new(&content_type) {
+            // Clients such as `curl -d` send JSON as form by default.
+            Some(BodyKind::Form) if is_json(&raw) => BodyKind::Json,
+            Some(kind) => kind,
+            None if raw.is_empty() || is_json(&raw) => BodyKind::Json,
+            None => BodyKind::Text,
+        };
+
+        let value = if raw.is_empty() {
+            serde_yaml::Value::Null
+        } else {
+            match kind {
+                BodyKind::Json => {
+                    serde_yaml::from_slice(&raw).map_err(|e| {
+                        ValidationError::new("$", format!("invalid JSON: {e}"))
+                    })?
+                }
+                BodyKind::Form => parse_form(&raw),
+                BodyKind::Multipart => {
+                    parse_multipart(&content_type, raw.clone()).await?
+                }
+                BodyKind::Xml | BodyKind::Text => serde_yaml::Value::String(
+                    String::from_utf8_lossy(&raw).into_owned(),
+                ),
+            }
+        };
+
+        Ok(Self {
+            kind,
+            content_type,
+            raw,
+            value,
+        })
+    }
+
+    /// Gets the body as text.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.raw).into_owned()
+    }
+}
+
+impl BodyKind {
+    /// Gets the body kind of the given content type, returns `None` when the
+    /// content type is missing or unknown.
+    pub fn new(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let kind = match mime.as_str() {
+            "application/json" | "application/yaml" | "text/yaml" => {
+                BodyKind::Json
+            }
+            "application/x-www-form-urlencoded" => BodyKind::Form,
+            "multipart/form-data" => BodyKind::Multipart,
+            "application/xml" | "text/xml" => BodyKind::Xml,
+            m if m.ends_with("+json") => BodyKind::Json,
+            m if m.ends_with("+xml") => BodyKind::Xml,
+            m if m.starts_with("text/") => BodyKind::Text,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// Checks whether the raw body is a valid JSON document.
+fn is_json(raw: &[u8]) -> bool {
+    serde_json::from_slice::<serde::de::IgnoredAny>(raw).is_ok()
+}
+
+/// Converts the textual field value to number or boolean when it
+/// represents one.
+pub fn scalar(value: &str) -> serde_yaml::Value {
+    match value {
+        "true" => return serde_yaml::Value::Bool(true),
+        "false" => return serde_yaml::Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(num) = value.parse::<i64>()
+        && num.to_string() == value
+    {
+        return serde_yaml::Value::Number(num.into());
+    }
+    if let Ok(num) = value.parse::<f64>()
+        && num.is_finite()
+        && num.to_string() == value
+    {
+        return serde_yaml::Value::Number(num.into());
+    }
+    serde_yaml::Value::String(value.to_owned())
+}
+
+/// Parses the URL query string into its fields.
+pub fn query_fields(query: Option<&str>) -> serde_yaml::Mapping {
+    let mut map = serde_yaml::Mapping::new();
+    for (name, value) in
+        form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+    {
+        insert_field(&mut map, &name, scalar(&value));
+    }
+    map
+}
+
+/// Gets the headers as fields with lowercase names.
+pub fn header_fields(headers: &HeaderMap) -> serde_yaml::Mapping {
+    let mut map = serde_yaml::Mapping::new();
+    for (name, value) in headers {
+        if let Ok(value) = value.to_str() {
+            insert_field(&mut map, name.as_str(), scalar(value));
+        }
+    }
+    map
+}
+
+/// Parses the `Cookie` headers into fields.
+pub fn cookie_fields(headers: &HeaderMap) -> serde_yaml::Mapping {
+    let mut map = serde_yaml::Mapping::new();
+    let cookies = headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'));
+    for cookie in cookies {
+        if let Some((name, value)) = cookie.trim().split_once('=') {
+            insert_field(&mut map, name, scalar(value));
+        }
+    }
+    map
+}
+
+/// Inserts the field into the mapping. Repeated fields are collected into
+/// a sequence.
+fn insert_field(
+    map: &mut serde_yaml::Mapping,
+    name: &str,
+    value: serde_yaml::Value,
+) {
+    let key = serde_yaml::Value::String(name.to_owned());
+    match map.get_mut(&key) {
+        Some(serde_yaml::Value::Sequence(seq)) => seq.push(value),
+        Some(prev) => {
+            let first = std::mem::take(prev);
+            *prev = serde_yaml::Value::Sequence(vec![first, value]);
+        }
+        None => {
+            map.insert(key, value);
+        }
+    }
+}
+
+fn parse_form(raw: &[u8]) -> serde_yaml::Value {
+    let form = String::from_utf8_lossy(raw);
+    serde_yaml::Value::Mapping(query_fields(Some(&form)))
+}
+
+/// Parses the multipart body. Text parts are mapped to their value, files
+/// to object with `filename`, `content_type` and `size` fields.
+async fn parse_multipart(
+    content_type: &str,
+    raw: Bytes,
+) -> Result<serde_yaml::Value, ValidationError> {
+    let invalid = |e: multer::Error| ValidationError::new("$", format!("{e}"));
+
+    let boundary = multer::parse_boundary(content_type).map_err(invalid)?;
+    let data = stream::once(async { Ok::<_, std::io::Error>(raw) });
+    let mut multipart = multer::Multipart::new(data, boundary);
+
+    let mut map = serde_yaml::Mapping::new();
+    while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+        let name = field.name().unwrap_or_default().to_owned();
+        let filename = field.file_name().map(str::to_owned);
+        let part_type = field.content_type().map(|m| m.to_string());
+        let bytes = field.bytes().await.map_err(invalid)?;
+
+        let value = match filename {
+            Some(filename) => {
+                let mut file = serde_yaml::Mapping::new();
+                file.insert("filename".into(), filename.into());
+                file.insert(
+                    "content_type".into(),
+                    part_type.map(Into::into).unwrap_or_default(),
+                );
+                file.insert("size".into(), (bytes.len() as u64).into());
+                serde_yaml::Value::Mapping(file)
+            }
+            None => scalar(&String::from_utf8_lossy(&bytes)),
+        };
+        insert_field(&mut map, &name, value);
+    }
+    Ok(serde_yaml::Value::Mapping(map))
+}
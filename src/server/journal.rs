@@ -0,0 +1,65 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// Maximum number of entries kept in the journal.
+const JOURNAL_CAPACITY: usize = 1000;
+
+/// Journal of the recent requests and WebSocket messages.
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Mutex<VecDeque<JournalEntry>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    /// Unix timestamp in milliseconds.
+    pub time: u128,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// HTTP request, status is missing when no response was sent.
+    Http {
+        method: String,
+        path: String,
+        status: Option<u16>,
+    },
+    /// Message received over WebSocket.
+    WsReceived { path: String, message: String },
+    /// Message sent over WebSocket.
+    WsSent { path: String, message: String },
+}
+
+impl Journal {
+    /// Records the event, dropping the oldest entry when full.
+    pub fn record(&self, event: JournalEvent) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= JOURNAL_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(JournalEntry { time, event });
+    }
+
+    /// Gets all the recorded entries from the oldest.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Removes all the recorded entries.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
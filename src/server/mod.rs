@@ -1,10 +1,28 @@
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full, combinators::BoxBody};
 use hyper::body::Bytes;
 
+pub mod admin;
 pub mod endpoint;
+pub mod graphql;
+pub mod grpc;
+pub mod incoming;
+pub mod journal;
+pub mod oidc;
+pub mod proxy;
+pub mod query;
+pub mod reload;
 pub mod router;
 pub mod router_node;
 pub mod server_struct;
+pub mod tls;
 pub mod url;
+pub mod websocket;
 
-pub type HyperRes = hyper::Response<Full<Bytes>>;
+/// Body of the HTTP response, which can be either sent at once or streamed.
+pub type ResBody = BoxBody<Bytes, std::io::Error>;
+pub type HyperRes = hyper::Response<ResBody>;
+
+/// Creates response body containing the whole given data.
+pub fn full_body(data: impl Into<Bytes>) -> ResBody {
+    Full::new(data.into()).map_err(|e| match e {}).boxed()
+}
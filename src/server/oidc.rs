@@ -0,0 +1,508 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use fake::rand::{self, Rng};
+use http_body_util::BodyExt;
+use hyper::{
+    Method, Request, StatusCode,
+    body::Incoming,
+    header::{AUTHORIZATION, CONTENT_TYPE, HOST, LOCATION},
+};
+use log::{error, info};
+use rsa::{
+    RsaPrivateKey,
+    pkcs1v15::{Signature, SigningKey, VerifyingKey},
+    sha2::{Digest, Sha256},
+    signature::{SignatureEncoding, Signer, Verifier},
+    traits::PublicKeyParts,
+};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::Result,
+    server::{HyperRes, full_body, query::parse_query},
+    specs::oidc::OidcConfig,
+};
+
+/// Key ID of the provider signing key.
+const KEY_ID: &str = "faupi";
+/// Size of the generated RSA key in bits.
+const KEY_BITS: usize = 2048;
+/// Time in which the authorization code must be exchanged for the tokens.
+const CODE_TTL: Duration = Duration::from_secs(600);
+
+/// Signing key shared by all the providers, so the issued tokens stay valid
+/// when the specification is reloaded.
+static SIGNING_KEY: OnceLock<RsaPrivateKey> = OnceLock::new();
+
+/// Built-in OpenID Connect provider, issuing tokens signed with locally
+/// generated RSA key.
+#[derive(Debug)]
+pub struct OidcProvider {
+    pub config: OidcConfig,
+    codes: Grants,
+    refresh_tokens: Grants,
+}
+
+/// Grants by their authorization code or refresh token. The grants expire
+/// after the `ttl` and the expired grants are removed on each access.
+#[derive(Debug)]
+struct Grants {
+    ttl: Duration,
+    grants: Mutex<HashMap<String, (Instant, Grant)>>,
+}
+
+/// Authorization granted to the client.
+#[derive(Debug, Clone)]
+struct Grant {
+    claims: Map<String, Value>,
+    client_id: Option<String>,
+    nonce: Option<String>,
+    scope: Option<String>,
+    /// PKCE code challenge, verified when exchanging the code.
+    challenge: Option<Challenge>,
+}
+
+/// PKCE code challenge of the authorization request.
+#[derive(Debug, Clone)]
+struct Challenge {
+    value: String,
+    /// `plain` or `S256`.
+    method: String,
+}
+
+impl OidcProvider {
+    /// Creates new provider with the given config. Starts generating the
+    /// signing key in background, when it doesn't exist yet.
+    pub fn new(config: OidcConfig) -> Result<Self> {
+        let config = config.normalize()?;
+        std::thread::spawn(signing_key);
+        let refresh_ttl = Duration::from_secs(config.refresh_expires_in);
+        Ok(Self {
+            config,
+            codes: Grants::new(CODE_TTL),
+            refresh_tokens: Grants::new(refresh_ttl),
+        })
+    }
+
+    /// Checks whether the given path is handled by the provider.
+    pub fn matches(&self, path: &str) -> bool {
+        path.strip_prefix(&self.config.prefix).is_some_and(|p| {
+            matches!(
+                p,
+                "/.well-known/openid-configuration"
+                    | "/.well-known/jwks.json"
+                    | "/authorize"
+                    | "/token"
+                    | "/userinfo"
+            )
+        })
+    }
+
+    /// Handles the provider request. The issuer scheme is `https` when the
+    /// server is `secure`.
+    pub async fn handle(
+        &self,
+        req: Request<Incoming>,
+        secure: bool,
+    ) -> HyperRes {
+        // The key generation takes a while, so it mustn't block the runtime
+        // when it's not done yet.
+        if SIGNING_KEY.get().is_none()
+            && let Err(e) = tokio::task::spawn_blocking(signing_key).await
+        {
+            error!("Generating OIDC signing key: {e}.");
+            return oauth_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+            );
+        }
+
+        let path = req.uri().path();
+        let path = path
+            .strip_prefix(&self.config.prefix)
+            .unwrap_or(path)
+            .to_owned();
+        let res = match (req.method(), path.as_str()) {
+            (&Method::GET, "/.well-known/openid-configuration") => {
+                json_res(StatusCode::OK, self.discovery(&req, secure))
+            }
+            (&Method::GET, "/.well-known/jwks.json") => {
+                json_res(StatusCode::OK, json!({ "keys": [jwk()] }))
+            }
+            (&Method::GET, "/authorize") => self.authorize(&req),
+            (&Method::POST, "/token") => self.token(req, secure).await,
+            (&Method::GET | &Method::POST, "/userinfo") => self.userinfo(&req),
+            _ => {
+                oauth_error(StatusCode::METHOD_NOT_ALLOWED, "invalid_request")
+            }
+        };
+        info!("OIDC request {path} -> response {}.", res.status());
+        res
+    }
+
+    /// Creates the OpenID provider metadata.
+    fn discovery(&self, req: &Request<Incoming>, secure: bool) -> Value {
+        let issuer = self.issuer(req, secure);
+        json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "userinfo_endpoint": format!("{issuer}/userinfo"),
+            "jwks_uri": format!("{issuer}/.well-known/jwks.json"),
+            "response_types_supported": ["code"],
+            "grant_types_supported": [
+                "authorization_code",
+                "password",
+                "client_credentials",
+                "refresh_token"
+            ],
+            "subject_types_supported": ["public"],
+            "id_token_signing_alg_values_supported": ["RS256"],
+            "scopes_supported": ["openid", "profile", "email"],
+            "token_endpoint_auth_methods_supported": [
+                "client_secret_basic",
+                "client_secret_post",
+                "none"
+            ],
+            "code_challenge_methods_supported": ["plain", "S256"],
+        })
+    }
+
+    /// Authorizes the user given by `login_hint` (or the first user) without
+    /// any login page and redirects back with the authorization code.
+    fn authorize(&self, req: &Request<Incoming>) -> HyperRes {
+        let query = parse_query(req.uri().query());
+        let Some(redirect) = query.get("redirect_uri") else {
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_request");
+        };
+        let Some(user) = self
+            .config
+            .user(query.get("login_hint").map(|s| s.as_str()))
+        else {
+            return oauth_error(StatusCode::BAD_REQUEST, "access_denied");
+        };
+
+        let challenge = match query.get("code_challenge") {
+            Some(value) => {
+                let method = query
+                    .get("code_challenge_method")
+                    .map_or("plain", |m| m.as_str());
+                if !matches!(method, "plain" | "S256") {
+                    return oauth_error(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_request",
+                    );
+                }
+                Some(Challenge {
+                    value: value.clone(),
+                    method: method.to_owned(),
+                })
+            }
+            None => None,
+        };
+
+        let code = self.codes.insert(Grant {
+            claims: user.claims(),
+            client_id: query.get("client_id").cloned(),
+            nonce: query.get("nonce").cloned(),
+            scope: query.get("scope").cloned(),
+            challenge,
+        });
+
+        let mut location = form_urlencoded::Serializer::new(String::new());
+        location.append_pair("code", &code);
+        if let Some(state) = query.get("state") {
+            location.append_pair("state", state);
+        }
+        let sep = if redirect.contains('?') { '&' } else { '?' };
+        hyper::Response::builder()
+            .status(StatusCode::FOUND)
+            .header(LOCATION, format!("{redirect}{sep}{}", location.finish()))
+            .body(full_body(""))
+            .unwrap()
+    }
+
+    /// Exchanges the grant for the tokens.
+    async fn token(&self, req: Request<Incoming>, secure: bool) -> HyperRes {
+        let issuer = self.issuer(&req, secure);
+        let basic_client = basic_client_id(&req);
+        let form = match req.into_body().collect().await {
+            Ok(body) => {
+                parse_query(std::str::from_utf8(&body.to_bytes()).ok())
+            }
+            Err(e) => {
+                error!("Failed to read token request body: {e}");
+                return oauth_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_request",
+                );
+            }
+        };
+        let client_id = form.get("client_id").cloned().or(basic_client);
+
+        let grant = match form.get("grant_type").map(|s| s.as_str()) {
+            Some("authorization_code") => form
+                .get("code")
+                .and_then(|c| self.codes.take(c))
+                .filter(|g| {
+                    g.challenge.as_ref().is_none_or(|c| {
+                        c.verify(form.get("code_verifier").map(|v| v.as_str()))
+                    })
+                }),
+            Some("refresh_token") => form
+                .get("refresh_token")
+                .and_then(|t| self.refresh_tokens.take(t)),
+            Some("password") => form
+                .get("username")
+                .and_then(|u| self.config.user(Some(u)))
+                .filter(|u| {
+                    u.password.is_none()
+                        || u.password == form.get("password").cloned()
+                })
+                .map(|u| Grant {
+                    claims: u.claims(),
+                    client_id: client_id.clone(),
+                    nonce: None,
+                    scope: form.get("scope").cloned(),
+                    challenge: None,
+                }),
+            Some("client_credentials") => client_id.as_ref().map(|id| Grant {
+                claims: Map::from_iter([("sub".into(), id.clone().into())]),
+                client_id: client_id.clone(),
+                nonce: None,
+                scope: form.get("scope").cloned(),
+                challenge: None,
+            }),
+            _ => {
+                return oauth_error(
+                    StatusCode::BAD_REQUEST,
+                    "unsupported_grant_type",
+                );
+            }
+        };
+
+        let Some(grant) = grant else {
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant");
+        };
+        json_res(StatusCode::OK, self.issue(&issuer, grant))
+    }
+
+    /// Returns claims of the user authenticated with the access token.
+    fn userinfo(&self, req: &Request<Incoming>) -> HyperRes {
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|a| a.to_str().ok())
+            .and_then(|a| a.strip_prefix("Bearer "));
+        let Some(mut claims) = token.and_then(verify) else {
+            let mut res =
+                oauth_error(StatusCode::UNAUTHORIZED, "invalid_token");
+            res.headers_mut().insert(
+                hyper::header::WWW_AUTHENTICATE,
+                "Bearer error=\"invalid_token\"".parse().unwrap(),
+            );
+            return res;
+        };
+
+        for claim in ["iss", "aud", "exp", "iat", "nonce", "scope"] {
+            claims.remove(claim);
+        }
+        json_res(StatusCode::OK, Value::Object(claims))
+    }
+
+    /// Issues the access, ID and refresh token for the given grant.
+    fn issue(&self, issuer: &str, grant: Grant) -> Value {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let mut claims = self.config.claims.clone();
+        claims.extend(grant.claims.clone());
+        claims.insert("iss".into(), issuer.into());
+        claims.insert(
+            "aud".into(),
+            grant.client_id.clone().unwrap_or("faupi".into()).into(),
+        );
+        claims.insert("iat".into(), now.into());
+        claims.insert("exp".into(), (now + self.config.expires_in).into());
+
+        let mut access = claims.clone();
+        if let Some(scope) = &grant.scope {
+            access.insert("scope".into(), scope.clone().into());
+        }
+        let mut id = claims;
+        if let Some(nonce) = &grant.nonce {
+            id.insert("nonce".into(), nonce.clone().into());
+        }
+
+        let refresh = self.refresh_tokens.insert(grant.clone());
+
+        let mut res = json!({
+            "access_token": sign_token(&access),
+            "token_type": "Bearer",
+            "expires_in": self.config.expires_in,
+            "id_token": sign_token(&id),
+            "refresh_token": refresh,
+        });
+        if let Some(scope) = grant.scope {
+            res["scope"] = scope.into();
+        }
+        res
+    }
+
+    /// Gets the token issuer, defaults to the requested host and the prefix.
+    fn issuer(&self, req: &Request<Incoming>, secure: bool) -> String {
+        if let Some(issuer) = &self.config.issuer {
+            return issuer.trim_end_matches('/').to_owned();
+        }
+        // HTTP/2 requests carry the host in the URI instead of the header.
+        let host = req
+            .headers()
+            .get(HOST)
+            .and_then(|h| h.to_str().ok())
+            .or_else(|| req.uri().authority().map(|a| a.as_str()))
+            .unwrap_or("localhost");
+        let scheme = if secure { "https" } else { "http" };
+        format!("{scheme}://{host}{}", self.config.prefix)
+    }
+}
+
+impl Grants {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            grants: Default::default(),
+        }
+    }
+
+    /// Stores the grant and returns its new random token.
+    fn insert(&self, grant: Grant) -> String {
+        let token = random_token();
+        let mut grants = self.grants.lock().unwrap();
+        self.prune(&mut grants);
+        grants.insert(token.clone(), (Instant::now(), grant));
+        token
+    }
+
+    /// Removes the grant with the given token, when it hasn't expired yet.
+    fn take(&self, token: &str) -> Option<Grant> {
+        let mut grants = self.grants.lock().unwrap();
+        self.prune(&mut grants);
+        grants.remove(token).map(|(_, grant)| grant)
+    }
+
+    fn prune(&self, grants: &mut HashMap<String, (Instant, Grant)>) {
+        grants.retain(|_, (issued, _)| issued.elapsed() < self.ttl);
+    }
+}
+
+impl Challenge {
+    /// Checks whether the code verifier matches the challenge.
+    fn verify(&self, verifier: Option<&str>) -> bool {
+        let Some(verifier) = verifier else {
+            return false;
+        };
+        match self.method.as_str() {
+            "S256" => {
+                let hash = Sha256::digest(verifier.as_bytes());
+                URL_SAFE_NO_PAD.encode(hash) == self.value
+            }
+            _ => verifier == self.value,
+        }
+    }
+}
+
+/// Gets the provider signing key, generating it when it doesn't exist yet.
+fn signing_key() -> &'static RsaPrivateKey {
+    SIGNING_KEY.get_or_init(|| {
+        let mut rng = rsa::rand_core::OsRng;
+        RsaPrivateKey::new(&mut rng, KEY_BITS)
+            .expect("failed to generate OIDC signing key")
+    })
+}
+
+/// Gets the public signing key in JWK format.
+fn jwk() -> Value {
+    let key = signing_key();
+    json!({
+        "kty": "RSA",
+        "use": "sig",
+        "alg": "RS256",
+        "kid": KEY_ID,
+        "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+    })
+}
+
+/// Creates JWT with the given claims signed using RS256.
+fn sign_token(claims: &Map<String, Value>) -> String {
+    let header = json!({ "alg": "RS256", "typ": "JWT", "kid": KEY_ID });
+    let data = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(Value::Object(claims.clone()).to_string()),
+    );
+
+    let key = SigningKey::<Sha256>::new(signing_key().clone());
+    let signature = key.sign(data.as_bytes());
+    format!("{data}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+}
+
+/// Verifies the JWT issued by the provider and returns its claims.
+fn verify(token: &str) -> Option<Map<String, Value>> {
+    let (data, signature) = token.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let key = VerifyingKey::<Sha256>::new(signing_key().to_public_key());
+    key.verify(
+        data.as_bytes(),
+        &Signature::try_from(signature.as_slice()).ok()?,
+    )
+    .ok()?;
+
+    let (_, payload) = data.split_once('.')?;
+    let claims: Map<String, Value> =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    match claims.get("exp").and_then(|e| e.as_u64()) {
+        Some(exp) if exp < now => None,
+        _ => Some(claims),
+    }
+}
+
+/// Gets the client ID from the basic authorization header.
+fn basic_client_id(req: &Request<Incoming>) -> Option<String> {
+    let creds = req
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded =
+        String::from_utf8(STANDARD.decode(creds.trim()).ok()?).ok()?;
+    decoded.split_once(':').map(|(id, _)| id.to_owned())
+}
+
+fn random_token() -> String {
+    format!("{:032x}", rand::rng().random::<u128>())
+}
+
+fn json_res(status: StatusCode, body: Value) -> HyperRes {
+    hyper::Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(full_body(body.to_string()))
+        .unwrap()
+}
+
+fn oauth_error(status: StatusCode, error: &str) -> HyperRes {
+    json_res(status, json!({ "error": error }))
+}
@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use http_body_util::BodyExt;
+use hyper::{
+    Request, StatusCode, Uri, Version,
+    body::Incoming,
+    header::{CONNECTION, HOST, HeaderValue},
+};
+use hyper_util::{
+    client::legacy::{Client, connect::HttpConnector},
+    rt::TokioExecutor,
+};
+use log::{info, warn};
+use pareg::{ArgError, FromArgStr};
+
+use crate::{
+    error::Result,
+    server::{HyperRes, full_body},
+};
+
+/// Upstream server the requests not matching any endpoint are forwarded to.
+/// The connections to the upstream are pooled and reused across requests.
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    upstream: Uri,
+    client: Client<HttpConnector, Incoming>,
+}
+
+impl Proxy {
+    /// Forwards the request to the upstream server. Responds with
+    /// `502 Bad Gateway` when the upstream can't be reached.
+    pub async fn forward(&self, req: Request<Incoming>) -> HyperRes {
+        let method = req.method().clone();
+        let path = req.uri().path().to_owned();
+        match self.send(req).await {
+            Ok(res) => {
+                info!(
+                    "Request {} {} -> proxied {}.",
+                    method,
+                    path,
+                    res.status()
+                );
+                res
+            }
+            Err(e) => {
+                warn!("Proxying {} {}: {e}.", method, path);
+                hyper::Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(full_body(format!("Bad gateway: {e}")))
+                    .unwrap()
+            }
+        }
+    }
+
+    async fn send(&self, req: Request<Incoming>) -> Result<HyperRes> {
+        // Validated when parsing the proxy.
+        let authority = self.upstream.authority().unwrap();
+        let (mut parts, body) = req.into_parts();
+        let base = self.upstream.path().trim_end_matches('/');
+        let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+        parts.uri = Uri::try_from(format!("http://{authority}{base}{path}"))
+            .map_err(hyper::http::Error::from)?;
+        parts.version = Version::HTTP_11;
+        parts.headers.remove(CONNECTION);
+        parts.headers.insert(
+            HOST,
+            HeaderValue::from_str(authority.as_str())
+                .map_err(hyper::http::Error::from)?,
+        );
+
+        let res = self
+            .client
+            .request(Request::from_parts(parts, body))
+            .await?;
+        Ok(res.map(|b| b.map_err(std::io::Error::other).boxed()))
+    }
+}
+
+impl FromStr for Proxy {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid =
+            |msg: &'static str| ArgError::parse_msg(msg, s.to_owned());
+        let upstream =
+            Uri::from_str(s).map_err(|_| invalid("expected upstream URL"))?;
+        if upstream.scheme_str() != Some("http") {
+            return Err(invalid("only `http` upstream is supported"));
+        }
+        if upstream.authority().is_none() {
+            return Err(invalid("expected upstream host"));
+        }
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        Ok(Self { upstream, client })
+    }
+}
+
+impl PartialEq for Proxy {
+    fn eq(&self, other: &Self) -> bool {
+        self.upstream == other.upstream
+    }
+}
+
+impl FromArgStr for Proxy {}
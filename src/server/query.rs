@@ -0,0 +1,12 @@
+use std::collections::HashMap;
+
+/// Parses URL query string into map of its parameters. When a parameter is
+/// repeated, the last value is used.
+pub fn parse_query(query: Option<&str>) -> HashMap<String, String> {
+    let Some(query) = query else {
+        return HashMap::new();
+    };
+    form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
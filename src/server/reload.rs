@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::server::router::Route;
+
+/// Routes added, removed and changed by the specification reload.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RouteDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+/// Result of the last specification reload.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadStatus {
+    /// Unix timestamp in milliseconds.
+    pub time: u128,
+    /// Error of the failed reload, the previous specification is kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Routes changed by the successful reload.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<RouteDiff>,
+}
+
+impl RouteDiff {
+    /// Compares the routes of the old and the new router.
+    pub fn new(
+        old: &HashMap<String, Route>,
+        new: &HashMap<String, Route>,
+    ) -> Self {
+        let mut diff = Self::default();
+        for (key, route) in new {
+            match old.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(prev) if prev.fingerprint != route.fingerprint => {
+                    diff.changed.push(key.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|key| !new.contains_key(*key))
+            .cloned()
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+}
+
+impl Display for RouteDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+impl ReloadStatus {
+    /// Creates status of the successful reload.
+    pub fn success(diff: RouteDiff) -> Self {
+        Self {
+            time: now(),
+            error: None,
+            diff: Some(diff),
+        }
+    }
+
+    /// Creates status of the failed reload.
+    pub fn failure(error: String) -> Self {
+        Self {
+            time: now(),
+            error: Some(error),
+            diff: None,
+        }
+    }
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
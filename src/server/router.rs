@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
-use http_body_util::Full;
-use hyper::{Method, StatusCode, body::Bytes};
+use hyper::{Method, StatusCode};
+use log::error;
 use tokio::sync::RwLock;
 
 use crate::{
@@ -9,48 +9,175 @@ use crate::{
     server::{
         HyperRes,
         endpoint::Endpoint,
+        full_body,
+        oidc::OidcProvider,
+        reload::{ReloadStatus, RouteDiff},
         router_node::RouterNode,
         url::{parser::UrlParser, var::UrlVar},
     },
-    specs::{body::body::Body, mock_config::MockConfig, spec::Spec},
+    specs::{
+        auth::Auth, body::body::Body, cors::Cors, fallback::Fallback,
+        mock_config::MockConfig, mount::has_prefix, rate_limit::RateLimit,
+        response::Response, spec::Spec,
+    },
 };
 
 pub type SharedRouter = Arc<RwLock<Router>>;
 
-#[derive(Debug)]
+/// Order of the methods listed in the `Allow` header.
+const METHOD_ORDER: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::OPTIONS,
+    Method::CONNECT,
+    Method::TRACE,
+];
+
+#[derive(Debug, Default)]
 pub struct Router {
     pub roots: HashMap<Method, RouterNode>,
-    pub not_found: HyperRes,
     pub templates: HashMap<String, Body>,
+    pub rate_limit: Option<RateLimit>,
+    pub auth: Option<Auth>,
+    pub oidc: Option<OidcProvider>,
+    pub cors: Option<Arc<Cors>>,
+    pub on_invalid: Option<Response>,
+    pub not_found: Option<Response>,
+    /// Fallbacks sorted from the longest prefix.
+    pub fallbacks: Vec<Fallback>,
+    /// CORS policies of the mount prefixes, sorted from the longest prefix.
+    pub mount_cors: Vec<(String, Arc<Cors>)>,
+    /// Endpoints by their route key.
+    pub routes: HashMap<String, Route>,
+    /// Result of the last specification reload, `None` before the first
+    /// reload.
+    pub reload: Option<ReloadStatus>,
+}
+
+/// Endpoint of the route with the fingerprint of its specification.
+#[derive(Debug)]
+pub struct Route {
+    pub fingerprint: Option<String>,
+    pub endpoint: Arc<Endpoint>,
 }
 
 impl Router {
     /// Creates new Router tree based on the given specification
     pub fn new(specs: MockConfig) -> Result<Self> {
+        Self::build(specs, None)
+    }
+
+    /// Creates new router from the reloaded specification. Endpoints with
+    /// unchanged specification are shared with this router, so that they
+    /// keep their state (response cycle, pinned response and rate limit
+    /// counters).
+    pub fn reload(&self, specs: MockConfig) -> Result<(Self, RouteDiff)> {
+        let router = Self::build(specs, Some(self))?;
+        let diff = RouteDiff::new(&self.routes, &router.routes);
+        Ok((router, diff))
+    }
+
+    fn build(specs: MockConfig, previous: Option<&Router>) -> Result<Self> {
         let mut router = Self::default();
         for spec in specs.specs {
-            router.insert(spec)?;
+            router.insert_from(spec, previous)?;
         }
         router.templates = specs.templates;
+        router.rate_limit = specs.rate_limit;
+        if let (Some(limit), Some(prev)) = (
+            &mut router.rate_limit,
+            previous.and_then(|p| p.rate_limit.as_ref()),
+        ) {
+            limit.keep_windows(prev);
+        }
+        router.auth = specs.auth;
+        router.oidc = specs.oidc.map(OidcProvider::new).transpose()?;
+        router.cors = specs.cors.map(Arc::new);
+        router.on_invalid = specs.on_invalid;
+        router.not_found = specs.not_found;
+        router.fallbacks = specs.fallbacks;
+        router
+            .fallbacks
+            .sort_by_key(|f| std::cmp::Reverse(f.prefix.len()));
+        router
+            .mount_cors
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
         Ok(router)
     }
 
     /// Inserts route to the route tree and converts the spec response to hyper
-    /// response.
-    pub fn insert(&mut self, spec: Spec) -> Result<()> {
+    /// response. The endpoint of the `previous` router is reused when the
+    /// route specification didn't change.
+    ///
+    /// Endpoints of the mounted specification files are inserted under the
+    /// mount prefix with the mount defaults.
+    fn insert_from(
+        &mut self,
+        mut spec: Spec,
+        previous: Option<&Router>,
+    ) -> Result<()> {
+        let key = spec.route_key()?;
+        let fingerprint = spec.fingerprint();
+        let url = spec.mounted_url();
+        if let Some(mount) = &spec.mount {
+            mount.apply(&mut spec.response);
+            spec.auth = spec.auth.or_else(|| mount.auth.clone());
+            if let Some(cors) = &mount.cors
+                && !self.mount_cors.iter().any(|(p, _)| *p == mount.prefix)
+            {
+                let cors = Arc::new(cors.clone());
+                self.mount_cors.push((mount.prefix.clone(), cors));
+            }
+        }
+
         let method = Method::from(spec.method);
         let root = self.roots.entry(method).or_default();
 
-        let mut chars = spec.url.chars();
+        let mut chars = url.chars();
         let mut parser = UrlParser::new(&mut chars);
         _ = parser.next()?;
 
-        let ep = Endpoint::new(spec.response).request(spec.request);
-        root.insert(parser, ep)?;
+        let prev = previous.and_then(|p| p.routes.get(&key));
+        let reused = prev
+            .filter(|r| fingerprint.is_some() && r.fingerprint == fingerprint)
+            .map(|r| r.endpoint.clone());
+        if let (Some(limit), Some(prev)) = (
+            &mut spec.rate_limit,
+            prev.and_then(|r| r.endpoint.rate_limit.as_ref()),
+        ) {
+            limit.keep_windows(prev);
+        }
+        let ep = match reused {
+            Some(ep) => ep,
+            None => Arc::new(
+                Endpoint::new(spec.response)
+                    .request(spec.request)
+                    .on_invalid(spec.on_invalid)
+                    .rate_limit(spec.rate_limit)
+                    .auth(spec.auth)
+                    .websocket(spec.websocket)
+                    .graphql(spec.graphql)
+                    .grpc(spec.grpc),
+            ),
+        };
+        root.insert(parser, ep.clone())?;
+        self.routes.insert(
+            key,
+            Route {
+                fingerprint,
+                endpoint: ep,
+            },
+        );
         Ok(())
     }
 
     /// Finds a response in the response tree, returns None when finding fails
+    ///
+    /// HEAD requests without HEAD endpoint are served by the GET endpoint.
     pub fn find(
         &self,
         method: &Method,
@@ -59,22 +186,81 @@ impl Router {
     ) -> Option<&Endpoint> {
         let mut url_parts = url.split("/");
         url_parts.next();
-        if let Some(root) = self.roots.get(method) {
-            return root.find(url_parts, vars);
+        let found = self
+            .roots
+            .get(method)
+            .and_then(|root| root.find(url_parts, vars));
+        if found.is_none() && method == Method::HEAD {
+            return self.find(&Method::GET, url, vars);
         }
-        None
+        found
     }
-}
 
-impl Default for Router {
-    fn default() -> Self {
-        Self {
-            roots: Default::default(),
-            not_found: hyper::Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Full::new(Bytes::from("Not found")))
-                .unwrap(),
-            templates: Default::default(),
+    /// Gets methods with endpoint matching the URL, including the implicit
+    /// HEAD and OPTIONS. Returns empty list when no endpoint matches.
+    pub fn allowed_methods(&self, url: &str) -> Vec<Method> {
+        let mut vars = HashMap::new();
+        let mut methods: Vec<_> = self
+            .roots
+            .keys()
+            .filter(|m| self.find(m, url, &mut vars).is_some())
+            .cloned()
+            .collect();
+        if methods.is_empty() {
+            return methods;
+        }
+
+        if methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
         }
+        if !methods.contains(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
+        }
+        methods.sort_by_key(|m| METHOD_ORDER.iter().position(|o| o == m));
+        methods
+    }
+
+    /// Gets the CORS policy of the mount with the longest prefix matching the
+    /// path, or the global CORS policy.
+    pub fn cors(&self, path: &str) -> Option<Arc<Cors>> {
+        self.mount_cors
+            .iter()
+            .find(|(prefix, _)| has_prefix(path, prefix))
+            .map(|(_, cors)| cors.clone())
+            .or_else(|| self.cors.clone())
+    }
+
+    /// Checks whether any of the prefix fallbacks matches the path.
+    pub fn has_fallback(&self, path: &str) -> bool {
+        self.fallbacks.iter().any(|f| f.matches(path))
+    }
+
+    /// Creates the response returned when no endpoint matches the request.
+    ///
+    /// Uses the fallback with the longest matching prefix, the configured
+    /// not found response or the default one. The templates can use the
+    /// `$method` and `$path` variables.
+    pub fn not_found(&self, method: &Method, path: &str) -> HyperRes {
+        let template = self
+            .fallbacks
+            .iter()
+            .find(|f| f.matches(path))
+            .map(|f| &f.response)
+            .or(self.not_found.as_ref());
+        if let Some(template) = template {
+            let vars = HashMap::from([
+                ("method".to_owned(), UrlVar::String(method.to_string())),
+                ("path".to_owned(), UrlVar::String(path.to_owned())),
+            ]);
+            match template.to_http_response(&vars, &self.templates) {
+                Ok(res) => return res,
+                Err(e) => error!("Creating not found response: {e}."),
+            }
+        }
+
+        hyper::Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full_body("Not found"))
+            .unwrap()
     }
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, str::Split};
+use std::{collections::HashMap, str::Split, sync::Arc};
 
 use crate::{
     error::Result,
@@ -13,13 +13,17 @@ use crate::{
 pub struct RouterNode {
     pub children: HashMap<String, RouterNode>,
     pub dyn_children: Vec<(UrlSegment, RouterNode)>,
-    pub endpoint: Option<Endpoint>,
+    pub endpoint: Option<Arc<Endpoint>>,
 }
 
 impl RouterNode {
     /// Inserts the given response to the router tree. When this node is a final
     /// node, sets its response, otherwise continues traversing.
-    pub fn insert(&mut self, mut url: UrlParser, ep: Endpoint) -> Result<()> {
+    pub fn insert(
+        &mut self,
+        mut url: UrlParser,
+        ep: Arc<Endpoint>,
+    ) -> Result<()> {
         let Some(segment) = url.next()? else {
             self.endpoint = Some(ep);
             return Ok(());
@@ -44,7 +48,7 @@ impl RouterNode {
         vars: &mut HashMap<String, UrlVar>,
     ) -> Option<&Endpoint> {
         let Some(part) = url.next() else {
-            return self.endpoint.as_ref();
+            return self.endpoint.as_deref();
         };
 
         if let Some(node) = self.children.get(part) {
@@ -1,30 +1,82 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
-use http_body_util::{BodyExt, Full};
+use http_body_util::BodyExt;
 use hyper::{
-    Request, Response, StatusCode,
-    body::{Bytes, Incoming},
-    header::{
-        ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
-        ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue,
-    },
-    server::conn::http1,
+    Method, Request, StatusCode,
+    body::{Body as _, Bytes, Incoming},
+    header::{ALLOW, CONTENT_LENGTH, HeaderValue, ORIGIN, WWW_AUTHENTICATE},
     service::service_fn,
 };
-use hyper_util::rt::TokioIo;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+};
 use log::{debug, error, info, warn};
-use tokio::{net::TcpListener, time::sleep};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    time::sleep,
+};
+use tokio_rustls::TlsAcceptor;
 
 use crate::{
     error::Result,
-    server::{endpoint::Endpoint, router::SharedRouter, url::var::UrlVar},
-    specs::body::body::Body,
+    server::{
+        HyperRes,
+        admin::{ADMIN_PREFIX, handle_admin},
+        endpoint::Endpoint,
+        full_body, graphql, grpc,
+        incoming::IncomingBody,
+        journal::{Journal, JournalEvent},
+        proxy::Proxy,
+        query::parse_query,
+        router::SharedRouter,
+        tls::TlsConfig,
+        url::var::UrlVar,
+        websocket,
+    },
+    specs::{
+        auth::{Auth, AuthOutcome},
+        body::body::Body,
+        cors::Cors,
+        delay::Delay,
+        rate_limit::{RateLimit, RateState},
+        request::Request as ExpectedRequest,
+        response::Response,
+        validation::ValidationError,
+    },
 };
 
+/// Header used to select specific response of the endpoint.
+const RESPONSE_HINT_HEADER: &str = "x-faupi-response";
+/// Query parameter used to select specific response of the endpoint.
+const RESPONSE_HINT_PARAM: &str = "faupi_response";
+
+/// Response body listing the request validation errors.
+#[derive(Serialize)]
+struct InvalidRequest {
+    message: &'static str,
+    errors: Vec<ValidationError>,
+}
+
 pub struct Server {
     listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    state: Arc<ServerState>,
+}
+
+/// State shared by all the connections of the server.
+struct ServerState {
     router: SharedRouter,
-    cors: bool,
+    /// CORS policy used when the specification doesn't define one.
+    cors: Option<Arc<Cors>>,
+    delay: Option<Delay>,
+    /// Upstream of the requests not matching any endpoint.
+    proxy: Option<Proxy>,
+    journal: Arc<Journal>,
+    /// Whether the server is served over TLS.
+    secure: bool,
 }
 
 impl Server {
@@ -32,12 +84,24 @@ impl Server {
     pub async fn new(
         addr: (&str, u16),
         router: SharedRouter,
-        cors: bool,
+        cors: Option<Cors>,
+        delay: Option<Delay>,
+        proxy: Option<Proxy>,
+        tls: Option<&TlsConfig>,
     ) -> Result<Self> {
+        let tls = tls.map(|t| t.acceptor(addr.0)).transpose()?;
+        let secure = tls.is_some();
         Ok(Self {
             listener: TcpListener::bind(addr).await?,
-            router,
-            cors,
+            tls,
+            state: Arc::new(ServerState {
+                router,
+                cors: cors.map(Arc::new),
+                delay,
+                proxy,
+                journal: Arc::default(),
+                secure,
+            }),
         })
     }
 
@@ -48,141 +112,421 @@ impl Server {
             .local_addr()
             .map(|a| a.to_string())
             .unwrap_or("-".to_owned());
-        info!("Server started on {addr}.");
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        info!("Server started on {scheme}://{addr}.");
 
-        let cors = self.cors;
         loop {
-            let (tcp, _) = self.listener.accept().await?;
-            let router = self.router.clone();
+            let (tcp, addr) = self.listener.accept().await?;
+            let state = self.state.clone();
+            let tls = self.tls.clone();
 
             tokio::task::spawn(async move {
-                let conn = http1::Builder::new().serve_connection(
-                    TokioIo::new(tcp),
-                    service_fn(move |req| {
-                        Server::handle_request(req, router.clone(), cors)
-                    }),
-                );
-                if let Err(e) = conn.await {
-                    error!("Serving connection: {e}.");
+                let Some(acceptor) = tls else {
+                    Server::serve_connection(tcp, state, addr).await;
+                    return;
+                };
+                match acceptor.accept(tcp).await {
+                    Ok(stream) => {
+                        Server::serve_connection(stream, state, addr).await
+                    }
+                    Err(e) => warn!("TLS handshake with {addr}: {e}."),
                 }
             });
         }
     }
 
-    /// Handles the HTTP request and returns the corresponding response
+    /// Serves HTTP connection over the given stream. The protocol (HTTP/1.1
+    /// or HTTP/2) is detected from the connection preface, so this handles
+    /// both HTTP/2 negotiated by ALPN and cleartext h2c with prior knowledge.
+    async fn serve_connection<S>(
+        stream: S,
+        state: Arc<ServerState>,
+        addr: SocketAddr,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let builder = auto::Builder::new(TokioExecutor::new());
+        let conn = builder.serve_connection_with_upgrades(
+            TokioIo::new(stream),
+            service_fn(move |req| {
+                Server::handle_request(req, state.clone(), addr)
+            }),
+        );
+        if let Err(e) = conn.await {
+            error!("Serving connection: {e}.");
+        }
+    }
+
+    /// Handles the HTTP request, applies the CORS policy and records the
+    /// request into the journal
     async fn handle_request(
         req: Request<Incoming>,
-        router: SharedRouter,
-        cors: bool,
-    ) -> Result<Response<Full<Bytes>>> {
-        if cors && req.method() == hyper::Method::OPTIONS {
-            let mut res = hyper::Response::builder()
-                .status(StatusCode::OK)
-                .body(Full::new(Bytes::new()))
-                .unwrap();
-            Self::inject_cors(res.headers_mut());
-            return Ok(res);
+        state: Arc<ServerState>,
+        addr: SocketAddr,
+    ) -> Result<HyperRes> {
+        let method = req.method().to_string();
+        let path = req.uri().path_and_query().map(|p| p.to_string());
+        let path = path.unwrap_or_else(|| req.uri().path().to_owned());
+        let is_head = req.method() == Method::HEAD;
+        let origin = req.headers().get(ORIGIN).cloned();
+
+        let cors = state.router.read().await.cors(req.uri().path());
+        let cors = cors.or_else(|| state.cors.clone());
+        let mut res = match &cors {
+            Some(cors) if Cors::is_preflight(req.method(), req.headers()) => {
+                let res = cors.preflight(req.headers());
+                info!("Preflight {} -> {}.", path, res.status());
+                Ok(res)
+            }
+            _ => {
+                let mut res = Self::respond(req, &state, addr).await;
+                if let (Some(cors), Ok(res)) = (&cors, &mut res) {
+                    let origin = origin.as_ref().and_then(|o| o.to_str().ok());
+                    cors.inject(origin, res.headers_mut());
+                }
+                res
+            }
+        };
+        if is_head {
+            res = res.map(Self::strip_body);
         }
+        if !path.starts_with(ADMIN_PREFIX) {
+            state.journal.record(JournalEvent::Http {
+                method,
+                path,
+                status: res.as_ref().ok().map(|r| r.status().as_u16()),
+            });
+        }
+        res
+    }
 
-        let router = router.read().await;
+    /// Handles the HTTP request and returns the corresponding response
+    async fn respond(
+        mut req: Request<Incoming>,
+        state: &ServerState,
+        addr: SocketAddr,
+    ) -> Result<HyperRes> {
+        let router = state.router.read().await;
         let mut vars = HashMap::new();
 
         let method = req.method().clone();
         let url = req.uri().path().to_string();
-        let Some(Endpoint { request, response }) =
-            router.find(&method, &url, &mut vars)
+        if url.starts_with(ADMIN_PREFIX) {
+            let res =
+                handle_admin(&method, req.uri(), &router, &state.journal);
+            info!("Admin request {} {} -> {}.", method, url, res.status());
+            return Ok(res);
+        }
+
+        if let Some(oidc) = &router.oidc
+            && oidc.matches(&url)
+        {
+            return Ok(oidc.handle(req, state.secure).await);
+        }
+
+        // The global rate limit is counted after the route matching, so that
+        // it can be keyed by the URL variables.
+        let found = router.find(&method, &url, &mut vars);
+        let mut rate = None;
+        if let Some(limit) = &router.rate_limit {
+            let state = Self::count_request(limit, &req, addr, &vars);
+            if state.exceeded {
+                info!("Request {} {} -> rate limit exceeded.", method, url);
+                return Ok(Self::rate_limited(state));
+            }
+            rate = Some(state);
+        }
+
+        let Some(Endpoint {
+            request,
+            response,
+            on_invalid,
+            rate_limit,
+            auth,
+            websocket,
+            graphql,
+            grpc,
+        }) = found
         else {
-            info!("Request {} {} -> response 404.", method, url);
-            return Ok(Self::finalize_res(router.not_found.clone(), cors));
+            // Endpoints with other methods and the prefix fallbacks take
+            // precedence over the proxy, only the global not found response
+            // is replaced by it.
+            let allowed = router.allowed_methods(&url);
+            if allowed.is_empty()
+                && !router.has_fallback(&url)
+                && let Some(proxy) = &state.proxy
+            {
+                drop(router);
+                return Ok(proxy.forward(req).await);
+            }
+            let mut res = if allowed.is_empty() {
+                router.not_found(&method, &url)
+            } else {
+                Self::not_allowed(&method, &allowed)
+            };
+            info!("Request {} {} -> response {}.", method, url, res.status());
+            if let Some(state) = rate {
+                state.inject(res.headers_mut());
+            }
+            return Ok(res);
         };
 
-        let response = response.get();
-        if let Some(delay) = response.delay {
-            sleep(Duration::from_millis(delay)).await;
+        if let Some(limit) = rate_limit {
+            let state = Self::count_request(limit, &req, addr, &vars);
+            if state.exceeded {
+                info!("Request {} {} -> rate limit exceeded.", method, url);
+                return Ok(Self::rate_limited(state));
+            }
+            rate = Some(state);
+        }
+
+        if let Some(auth) = auth.as_ref().or(router.auth.as_ref()) {
+            let query = parse_query(req.uri().query());
+            match auth.authenticate(req.headers(), &query) {
+                AuthOutcome::Authorized(claims) => vars.extend(claims),
+                outcome => {
+                    info!("Request {} {} -> unauthorized.", method, url);
+                    let res = Self::unauthorized(auth, outcome);
+                    return Ok(res);
+                }
+            }
+        }
+
+        if let Some(ws) = websocket {
+            let res = websocket::upgrade(
+                &mut req,
+                ws,
+                vars,
+                &router.templates,
+                state.journal.clone(),
+            );
+            info!("Request {} {} -> response {}.", method, url, res.status());
+            return Ok(res);
         }
 
-        if let Some(exp_body) = request
-            && let Err(res) =
-                Server::validate_req(req, exp_body, &vars, &router.templates)
-                    .await
+        let hint = Self::response_hint(&req);
+        if let Some(spec) = grpc {
+            let res = grpc::handle(
+                req,
+                spec,
+                response,
+                hint.as_deref(),
+                state.delay.as_ref(),
+                vars,
+                &router.templates,
+            )
+            .await;
+            info!("Request {} {} -> gRPC response.", method, url);
+            return Ok(res);
+        }
+
+        let response = response.get(hint.as_deref());
+        if let Some(delay) = response.delay.as_ref().or(state.delay.as_ref()) {
+            sleep(delay.sample()).await;
+        }
+
+        if let Some(spec) = graphql {
+            let res =
+                graphql::handle(req, spec, vars, &router.templates).await;
+            info!("Request {} {} -> response {}.", method, url, res.status());
+            return Ok(res);
+        }
+
+        if let Some(exp) = request
+            && let Err(res) = Server::validate_req(
+                req,
+                exp,
+                on_invalid.as_ref().or(router.on_invalid.as_ref()),
+                &mut vars,
+                &router.templates,
+            )
+            .await
         {
-            info!("Request {} {} -> Failed body validation.", method, url);
-            return Ok(Self::finalize_res(res, cors));
+            info!("Request {} {} -> Failed validation.", method, url);
+            return Ok(res);
         }
 
-        let hyper_res = response.to_http_response(&vars, &router.templates)?;
+        let mut hyper_res =
+            response.to_http_response(&vars, &router.templates)?;
+        if let Some(state) = rate {
+            state.inject(hyper_res.headers_mut());
+        }
         debug!(
             "Request: {} {} -> response {}",
             method, url, response.status.0
         );
-        Ok(Self::finalize_res(hyper_res, cors))
+        Ok(hyper_res)
     }
 
-    async fn validate_req(
-        req: Request<Incoming>,
-        exp_body: &Body,
+    /// Counts the request into the given rate limit.
+    fn count_request(
+        limit: &RateLimit,
+        req: &Request<Incoming>,
+        addr: SocketAddr,
         vars: &HashMap<String, UrlVar>,
-        templates: &HashMap<String, Body>,
-    ) -> std::result::Result<(), Response<Full<Bytes>>> {
-        let inc_bytes = match req.into_body().collect().await {
-            Ok(collected) => collected.to_bytes(),
-            Err(e) => {
-                error!("Failed to read request body: {}", e);
-                let err_res = hyper::Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(Bytes::from("Failed to read body")))
-                    .unwrap();
-                return Err(err_res);
-            }
-        };
+    ) -> RateState {
+        let key = limit.key.resolve(req.headers(), addr.ip(), vars);
+        limit.check(key)
+    }
 
-        let inc_json: serde_yaml::Value = if inc_bytes.is_empty() {
-            serde_yaml::Value::Null
+    /// Creates response for requests with method the URL doesn't allow.
+    /// OPTIONS requests get the list of the allowed methods.
+    fn not_allowed(method: &Method, allowed: &[Method]) -> HyperRes {
+        let allow = allowed
+            .iter()
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (status, body) = if method == Method::OPTIONS {
+            (StatusCode::NO_CONTENT, "")
         } else {
-            serde_yaml::from_slice(&inc_bytes).unwrap_or_else(|_| {
-                warn!("Incoming request body is not valid JSON/YAML.");
-                serde_yaml::Value::Null
-            })
+            (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
         };
+        hyper::Response::builder()
+            .status(status)
+            .header(ALLOW, allow)
+            .body(full_body(body))
+            .unwrap()
+    }
 
-        if !exp_body.validate(&inc_json, vars, templates) {
-            let bad_req = hyper::Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Full::new(Bytes::from(
-                    "Request body does not match expected scheme.",
-                )))
-                .unwrap();
-            return Err(bad_req);
+    /// Removes body of the response to HEAD request, keeping its length.
+    fn strip_body(res: HyperRes) -> HyperRes {
+        let (mut parts, body) = res.into_parts();
+        if let Some(len) = body.size_hint().exact()
+            && !parts.headers.contains_key(CONTENT_LENGTH)
+        {
+            parts.headers.insert(CONTENT_LENGTH, len.into());
         }
-        Ok(())
+        hyper::Response::from_parts(parts, full_body(Bytes::new()))
+    }
+
+    /// Creates response for requests exceeding the rate limit.
+    fn rate_limited(state: RateState) -> HyperRes {
+        let mut res = hyper::Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .body(full_body("Too many requests"))
+            .unwrap();
+        state.inject(res.headers_mut());
+        res
     }
 
-    /// Finalizes response - adds CORS headers when configured
-    fn finalize_res(
-        mut res: Response<Full<Bytes>>,
-        cors: bool,
-    ) -> Response<Full<Bytes>> {
-        if cors {
-            Self::inject_cors(res.headers_mut());
+    /// Creates response for requests failing the authentication.
+    fn unauthorized(auth: &Auth, outcome: AuthOutcome) -> HyperRes {
+        let (status, msg) = match &outcome {
+            AuthOutcome::Forbidden(msg) => {
+                (StatusCode::FORBIDDEN, msg.clone())
+            }
+            AuthOutcome::Invalid(msg) | AuthOutcome::InvalidToken(msg) => {
+                (StatusCode::UNAUTHORIZED, format!("Unauthorized: {msg}"))
+            }
+            _ => (StatusCode::UNAUTHORIZED, "Unauthorized".to_owned()),
+        };
+
+        let mut res = hyper::Response::builder()
+            .status(status)
+            .body(full_body(msg))
+            .unwrap();
+        if status == StatusCode::UNAUTHORIZED {
+            for challenge in auth.challenges(&outcome) {
+                if let Ok(value) = HeaderValue::from_str(&challenge) {
+                    res.headers_mut().append(WWW_AUTHENTICATE, value);
+                }
+            }
         }
         res
     }
 
-    /// Helper function to inject CORS headers into a response
-    fn inject_cors(headers: &mut hyper::HeaderMap) {
-        headers.insert(
-            ACCESS_CONTROL_ALLOW_ORIGIN,
-            HeaderValue::from_static("*"),
-        );
-        headers.insert(
-            ACCESS_CONTROL_ALLOW_METHODS,
-            HeaderValue::from_static(
-                "GET, POST, PUT, DELETE, PATCH, OPTIONS, HEAD",
-            ),
-        );
-        headers.insert(
-            ACCESS_CONTROL_ALLOW_HEADERS,
-            HeaderValue::from_static("*"),
+    /// Gets the response selection hint from the `X-Faupi-Response` header or
+    /// the `faupi_response` query parameter.
+    fn response_hint(req: &Request<Incoming>) -> Option<String> {
+        if let Some(hint) = req.headers().get(RESPONSE_HINT_HEADER) {
+            return hint.to_str().ok().map(|h| h.to_owned());
+        }
+        parse_query(req.uri().query()).remove(RESPONSE_HINT_PARAM)
+    }
+
+    async fn validate_req(
+        req: Request<Incoming>,
+        exp: &ExpectedRequest,
+        on_invalid: Option<&Response>,
+        vars: &mut HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> std::result::Result<(), HyperRes> {
+        let (parts, body) = req.into_parts();
+        let mut errors = exp.validate_params(
+            parts.uri.query(),
+            &parts.headers,
+            vars,
+            templates,
         );
+        let params_valid = errors.is_empty();
+
+        if let Some(exp_body) = &exp.body {
+            let inc_bytes = match body.collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) => {
+                    error!("Failed to read request body: {}", e);
+                    let err_res = hyper::Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(full_body("Failed to read body"))
+                        .unwrap();
+                    return Err(err_res);
+                }
+            };
+
+            match IncomingBody::parse(&parts.headers, inc_bytes).await {
+                Ok(inc) => {
+                    errors.extend(exp_body.validate(&inc, vars, templates))
+                }
+                Err(e) => {
+                    warn!(
+                        "Incoming request body is not valid: {}.",
+                        e.message
+                    );
+                    errors.push(e);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let message = if params_valid {
+            "Request body does not match expected scheme."
+        } else {
+            "Request does not match expected scheme."
+        };
+        if let Some(template) = on_invalid {
+            let value = serde_yaml::to_value(&errors).unwrap_or_default();
+            vars.insert("errors".into(), UrlVar::Value(value));
+            vars.insert("message".into(), UrlVar::String(message.into()));
+            return Err(template
+                .to_http_response(vars, templates)
+                .unwrap_or_else(|e| {
+                    error!("Creating invalid request response: {e}.");
+                    Self::invalid_request(exp, message, errors)
+                }));
+        }
+        Err(Self::invalid_request(exp, message, errors))
+    }
+
+    /// Creates the default response to the request failing the validation.
+    fn invalid_request(
+        exp: &ExpectedRequest,
+        message: &'static str,
+        errors: Vec<ValidationError>,
+    ) -> HyperRes {
+        let body = InvalidRequest { message, errors };
+        let status = exp
+            .status
+            .clone()
+            .and_then(|s| s.try_into().ok())
+            .unwrap_or(StatusCode::BAD_REQUEST);
+        hyper::Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(full_body(serde_json::to_string(&body).unwrap_or_default()))
+            .unwrap()
     }
 }
@@ -0,0 +1,59 @@
+use std::{path::PathBuf, sync::Arc};
+
+use log::info;
+use rustls::{ServerConfig, crypto::ring::default_provider};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use tokio_rustls::TlsAcceptor;
+
+use crate::error::Result;
+
+/// Source of the server TLS certificate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TlsConfig {
+    /// Certificate chain and private key loaded from PEM files.
+    Files { cert: PathBuf, key: PathBuf },
+    /// Self-signed certificate generated on startup.
+    SelfSigned,
+}
+
+impl TlsConfig {
+    /// Creates TLS acceptor with the configured certificate.
+    pub fn acceptor(&self, host: &str) -> Result<TlsAcceptor> {
+        let (certs, key) = match self {
+            TlsConfig::Files { cert, key } => {
+                let certs = CertificateDer::pem_file_iter(cert)?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                (certs, PrivateKeyDer::from_pem_file(key)?)
+            }
+            TlsConfig::SelfSigned => Self::self_signed(host)?,
+        };
+
+        let mut config =
+            ServerConfig::builder_with_provider(Arc::new(default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_no_client_auth()
+                .with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    /// Generates self-signed certificate for localhost and the given host.
+    fn self_signed(
+        host: &str,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+        let mut names = vec![
+            "localhost".to_owned(),
+            "127.0.0.1".to_owned(),
+            "::1".to_owned(),
+        ];
+        if !names.iter().any(|n| n == host) {
+            names.push(host.to_owned());
+        }
+
+        let cert = rcgen::generate_simple_self_signed(names)?;
+        info!("Generated self-signed TLS certificate.");
+        let key = PrivateKeyDer::try_from(cert.signing_key.serialize_der())
+            .map_err(|e| crate::error::Error::Msg(e.to_owned()))?;
+        Ok((vec![cert.cert.der().clone()], key))
+    }
+}
@@ -16,6 +16,7 @@ impl<'a> UrlParser<'a> {
     }
 
     /// Gets next URL segment.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<UrlSegment>> {
         if self.cur.is_none() {
             return Ok(None);
@@ -97,7 +98,7 @@ impl<'a> UrlParser<'a> {
             ident.push(c);
             self.cur = self.chars.next();
         }
-        return Ok(ident);
+        Ok(ident)
     }
 
     /// Reads leading whitespaces.
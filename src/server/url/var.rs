@@ -4,6 +4,8 @@ use std::fmt::Display;
 pub enum UrlVar {
     String(String),
     Number(u32),
+    /// Structured value, such as a JWT claim.
+    Value(serde_yaml::Value),
 }
 
 impl Default for UrlVar {
@@ -17,6 +19,10 @@ impl Display for UrlVar {
         match self {
             UrlVar::String(s) => write!(f, "{s}"),
             UrlVar::Number(n) => write!(f, "{n}"),
+            UrlVar::Value(serde_yaml::Value::String(s)) => write!(f, "{s}"),
+            UrlVar::Value(v) => {
+                write!(f, "{}", serde_json::to_string(v).unwrap_or_default())
+            }
         }
     }
 }
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::{
+    Request, StatusCode,
+    body::Incoming,
+    header::{
+        CONNECTION, HeaderMap, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY,
+        UPGRADE,
+    },
+    upgrade::Upgraded,
+};
+use hyper_util::rt::TokioIo;
+use log::{debug, error};
+use tokio::{sync::mpsc, task::JoinSet, time::sleep};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{Message, handshake::derive_accept_key, protocol::Role},
+};
+
+use crate::server::{
+    HyperRes, full_body,
+    journal::{Journal, JournalEvent},
+    url::var::UrlVar,
+};
+use crate::specs::{body::body::Body, websocket::WebSocket};
+
+/// State of single WebSocket connection.
+struct Session {
+    spec: WebSocket,
+    path: String,
+    vars: HashMap<String, UrlVar>,
+    templates: HashMap<String, Body>,
+    journal: Arc<Journal>,
+}
+
+/// Checks whether the request asks for WebSocket upgrade.
+pub fn is_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name, token: &str| {
+        headers.get_all(name).iter().any(|v| {
+            v.to_str().is_ok_and(|v| {
+                v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token))
+            })
+        })
+    };
+    has_token(CONNECTION, "upgrade")
+        && has_token(UPGRADE, "websocket")
+        && headers.contains_key(SEC_WEBSOCKET_KEY)
+}
+
+/// Upgrades the connection to WebSocket and starts the scripted session.
+/// Returns the handshake response, or 426 when the request isn't WebSocket
+/// upgrade request.
+pub fn upgrade(
+    req: &mut Request<Incoming>,
+    spec: &WebSocket,
+    vars: HashMap<String, UrlVar>,
+    templates: &HashMap<String, Body>,
+    journal: Arc<Journal>,
+) -> HyperRes {
+    let key = req.headers().get(SEC_WEBSOCKET_KEY);
+    let Some(key) = key.filter(|_| is_upgrade(req.headers())) else {
+        return hyper::Response::builder()
+            .status(StatusCode::UPGRADE_REQUIRED)
+            .header(UPGRADE, "websocket")
+            .body(full_body("WebSocket upgrade required"))
+            .unwrap();
+    };
+    let accept = derive_accept_key(key.as_bytes());
+
+    let session = Session {
+        spec: spec.clone(),
+        path: req.uri().path().to_owned(),
+        vars,
+        templates: templates.clone(),
+        journal,
+    };
+    let on_upgrade = hyper::upgrade::on(req);
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => Arc::new(session).run(upgraded).await,
+            Err(e) => error!("WebSocket upgrade: {e}."),
+        }
+    });
+
+    hyper::Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "Upgrade")
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_ACCEPT, accept)
+        .body(full_body(""))
+        .unwrap()
+}
+
+impl Session {
+    /// Runs the session until the connection is closed.
+    async fn run(self: Arc<Self>, upgraded: Upgraded) {
+        let stream = WebSocketStream::from_raw_socket(
+            TokioIo::new(upgraded),
+            Role::Server,
+            None,
+        )
+        .await;
+        let (mut sink, mut stream) = stream.split();
+        debug!("WebSocket {} connected.", self.path);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Dropping the set aborts the timers when the session ends
+        let mut timers = JoinSet::new();
+        for id in 0..self.spec.messages.len() {
+            let (session, tx) = (self.clone(), tx.clone());
+            timers.spawn(async move { session.schedule(id, tx).await });
+        }
+
+        loop {
+            tokio::select! {
+                Some(msg) = rx.recv() => {
+                    self.journal.record(JournalEvent::WsSent {
+                        path: self.path.clone(),
+                        message: msg.clone(),
+                    });
+                    if sink.send(Message::text(msg)).await.is_err() {
+                        break;
+                    }
+                }
+                msg = stream.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        self.journal.record(JournalEvent::WsReceived {
+                            path: self.path.clone(),
+                            message: text.to_string(),
+                        });
+                        if let Some(reply) = self.reply(&text) {
+                            _ = tx.send(reply);
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                        break;
+                    }
+                    _ => {}
+                },
+            }
+        }
+        debug!("WebSocket {} disconnected.", self.path);
+    }
+
+    /// Sends the scripted message with the given index based on its timing.
+    async fn schedule(&self, id: usize, tx: mpsc::UnboundedSender<String>) {
+        let msg = &self.spec.messages[id];
+        if let Some(after) = msg.after {
+            sleep(Duration::from_millis(after)).await;
+        }
+        loop {
+            if tx.send(self.render(&msg.body, &self.vars)).is_err() {
+                return;
+            }
+            let Some(every) = msg.every else {
+                return;
+            };
+            sleep(Duration::from_millis(every)).await;
+        }
+    }
+
+    /// Renders reply to the incoming message, if any matches.
+    fn reply(&self, msg: &str) -> Option<String> {
+        let mut vars = self.vars.clone();
+        let body = self.spec.reply(msg, &mut vars)?;
+        Some(self.render(body, &vars))
+    }
+
+    /// Renders the message body. Strings are sent as is, other values are
+    /// serialized to JSON.
+    fn render(&self, body: &Body, vars: &HashMap<String, UrlVar>) -> String {
+        match body.resolve(vars, &self.templates) {
+            serde_yaml::Value::String(s) => s,
+            value => serde_json::to_string(&value).unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::{Sha256, Sha384, Sha512};
+
+/// Decodes the JWT and returns its claims.
+///
+/// When `secret` is given, the token signature is verified using the HMAC
+/// algorithm from the token header. Otherwise the signature is not checked.
+/// Expired tokens (based on the `exp` claim) are rejected.
+pub fn decode_jwt(
+    token: &str,
+    secret: Option<&str>,
+) -> Result<Map<String, Value>, String> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("malformed token".into());
+    };
+
+    let header = decode_part(header)?;
+    if let Some(secret) = secret {
+        let alg = header.get("alg").and_then(|a| a.as_str()).unwrap_or("");
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "malformed token signature")?;
+        let data = &token[..header_payload_len(token)];
+        if sign(alg, secret.as_bytes(), data.as_bytes())? != signature {
+            return Err("invalid token signature".into());
+        }
+    }
+
+    let claims = decode_part(payload)?;
+    if let Some(exp) = claims.get("exp").and_then(|e| e.as_f64()) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        if exp < now {
+            return Err("token expired".into());
+        }
+    }
+    Ok(claims)
+}
+
+/// Signs the given data using the HMAC algorithm with the given name.
+pub fn sign(alg: &str, secret: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    macro_rules! hmac {
+        ($hash:ty) => {{
+            let mut mac = Hmac::<$hash>::new_from_slice(secret)
+                .map_err(|e| e.to_string())?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }};
+    }
+
+    match alg {
+        "HS256" => hmac!(Sha256),
+        "HS384" => hmac!(Sha384),
+        "HS512" => hmac!(Sha512),
+        _ => Err(format!("unsupported token algorithm '{alg}'")),
+    }
+}
+
+fn decode_part(part: &str) -> Result<Map<String, Value>, String> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| "malformed token encoding")?;
+    serde_json::from_slice(&bytes).map_err(|_| "malformed token JSON".into())
+}
+
+/// Gets length of the signed part of the token (header and payload).
+fn header_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
@@ -0,0 +1,238 @@
+mod jwt;
+
+pub use jwt::{decode_jwt, sign};
+
+use std::collections::HashMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hyper::{HeaderMap, header::AUTHORIZATION};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::server::url::var::UrlVar;
+
+/// Authentication required by the endpoint. The request is authenticated when
+/// it satisfies any of the configured schemes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Auth {
+    #[serde(default = "default_realm")]
+    pub realm: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub basic: Vec<BasicAuth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<ApiKeyAuth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<BearerAuth>,
+}
+
+/// Basic authentication credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// API key passed in header or query parameter. When no keys are given, any
+/// key is accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyAuth {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+}
+
+/// Bearer token authentication. When neither tokens nor JWT is configured,
+/// any token is accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BearerAuth {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<JwtAuth>,
+}
+
+/// Bearer token in JWT format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtAuth {
+    /// HMAC secret used to verify the token, signature isn't verified when
+    /// not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Claims the token must contain, otherwise the request is forbidden.
+    /// Kept in order, so that the endpoint fingerprint is stable.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub claims: IndexMap<String, serde_json::Value>,
+}
+
+/// Result of the request authentication.
+#[derive(Debug)]
+pub enum AuthOutcome {
+    /// Authenticated, contains the variables (JWT claims) of the request.
+    Authorized(HashMap<String, UrlVar>),
+    /// No credentials were given.
+    Missing,
+    /// Given credentials are invalid.
+    Invalid(String),
+    /// Given bearer token is invalid.
+    InvalidToken(String),
+    /// Credentials are valid, but don't grant access.
+    Forbidden(String),
+}
+
+impl Auth {
+    /// Authenticates the request with the given headers and query.
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        query: &HashMap<String, String>,
+    ) -> AuthOutcome {
+        let authorization = headers
+            .get(AUTHORIZATION)
+            .and_then(|a| a.to_str().ok())
+            .unwrap_or_default();
+        let mut invalid = AuthOutcome::Missing;
+
+        if !self.basic.is_empty()
+            && let Some(creds) = authorization.strip_prefix("Basic ")
+        {
+            if self.check_basic(creds) {
+                return AuthOutcome::Authorized(HashMap::new());
+            }
+            invalid = AuthOutcome::Invalid("invalid credentials".into());
+        }
+
+        if let Some(api_key) = &self.api_key
+            && let Some(key) = api_key.find(headers, query)
+        {
+            if api_key.keys.is_empty() || api_key.keys.iter().any(|k| k == key)
+            {
+                return AuthOutcome::Authorized(HashMap::new());
+            }
+            invalid = AuthOutcome::Invalid("invalid API key".into());
+        }
+
+        if let Some(bearer) = &self.bearer
+            && let Some(token) = authorization.strip_prefix("Bearer ")
+        {
+            match bearer.check(token.trim()) {
+                outcome @ AuthOutcome::InvalidToken(_) => invalid = outcome,
+                outcome => return outcome,
+            }
+        }
+
+        invalid
+    }
+
+    /// Gets the `WWW-Authenticate` header values of the configured schemes.
+    pub fn challenges(&self, outcome: &AuthOutcome) -> Vec<String> {
+        let mut res = vec![];
+        if !self.basic.is_empty() {
+            res.push(format!("Basic realm=\"{}\"", self.realm));
+        }
+        if self.bearer.is_some() {
+            match outcome {
+                AuthOutcome::InvalidToken(msg) => res.push(format!(
+                    "Bearer realm=\"{}\", error=\"invalid_token\", \
+                    error_description=\"{msg}\"",
+                    self.realm
+                )),
+                _ => res.push(format!("Bearer realm=\"{}\"", self.realm)),
+            }
+        }
+        if let Some(ApiKeyAuth {
+            header: Some(header),
+            ..
+        }) = &self.api_key
+        {
+            res.push(format!(
+                "ApiKey realm=\"{}\", header=\"{header}\"",
+                self.realm
+            ));
+        }
+        res
+    }
+
+    fn check_basic(&self, creds: &str) -> bool {
+        let Some(decoded) = STANDARD
+            .decode(creds.trim())
+            .ok()
+            .and_then(|d| String::from_utf8(d).ok())
+        else {
+            return false;
+        };
+        let Some((user, pass)) = decoded.split_once(':') else {
+            return false;
+        };
+        self.basic
+            .iter()
+            .any(|b| b.username == user && b.password == pass)
+    }
+}
+
+impl ApiKeyAuth {
+    fn find<'a>(
+        &self,
+        headers: &'a HeaderMap,
+        query: &'a HashMap<String, String>,
+    ) -> Option<&'a str> {
+        let header = self
+            .header
+            .as_ref()
+            .and_then(|h| headers.get(h))
+            .and_then(|v| v.to_str().ok());
+        header.or_else(|| {
+            self.query
+                .as_ref()
+                .and_then(|q| query.get(q))
+                .map(|s| s.as_str())
+        })
+    }
+}
+
+impl BearerAuth {
+    fn check(&self, token: &str) -> AuthOutcome {
+        if self.tokens.iter().any(|t| t == token) {
+            return AuthOutcome::Authorized(HashMap::new());
+        }
+
+        let Some(jwt) = &self.jwt else {
+            if self.tokens.is_empty() {
+                return AuthOutcome::Authorized(HashMap::new());
+            }
+            return AuthOutcome::InvalidToken("invalid token".into());
+        };
+
+        let claims = match decode_jwt(token, jwt.secret.as_deref()) {
+            Ok(claims) => claims,
+            Err(e) => return AuthOutcome::InvalidToken(e),
+        };
+        for (name, exp) in jwt.claims.iter() {
+            if claims.get(name) != Some(exp) {
+                return AuthOutcome::Forbidden(format!(
+                    "token claim '{name}' doesn't match"
+                ));
+            }
+        }
+
+        let vars = claims
+            .into_iter()
+            .filter_map(|(name, val)| {
+                let val = serde_yaml::to_value(val).ok()?;
+                Some((format!("jwt.{name}"), UrlVar::Value(val)))
+            })
+            .collect();
+        AuthOutcome::Authorized(vars)
+    }
+}
+
+fn default_realm() -> String {
+    "faupi".into()
+}
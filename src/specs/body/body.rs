@@ -8,10 +8,13 @@ use std::{
 use crate::{
     error::{self, Error},
     server::url::{error::UrlError, var::UrlVar},
-    specs::body::{
-        Mapping, Sequence, TaggedValue,
-        dynamic::{Dynamic, DynamicValue},
-        type_constraint::TypeConstraint,
+    specs::{
+        body::{
+            Mapping, Sequence, TaggedValue,
+            dynamic::{Dynamic, DynamicValue},
+            type_constraint::{TYPE_MARKER, TypeConstraint},
+        },
+        validation::{ValidationError, index_path, key_path},
     },
 };
 
@@ -27,7 +30,7 @@ pub enum Body {
     Mapping(Mapping),
     Tagged(Box<TaggedValue>),
     Dynamic(Dynamic),
-    Constraint(TypeConstraint),
+    Constraint(Box<TypeConstraint>),
 }
 
 impl Body {
@@ -79,71 +82,113 @@ impl Body {
         }
     }
 
+    /// Checks whether the incoming value matches the body.
     pub fn validate(
         &self,
         inc: &serde_yaml::Value,
         vars: &HashMap<String, UrlVar>,
         templates: &HashMap<String, Body>,
     ) -> bool {
+        let mut errors = vec![];
+        self.check(inc, vars, templates, "$", &mut errors);
+        errors.is_empty()
+    }
+
+    /// Checks the incoming value on the given JSON `path` against the body
+    /// and collects all the found mismatches into `errors`.
+    pub fn check(
+        &self,
+        inc: &serde_yaml::Value,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
         match self {
             Body::Sequence(items) => {
                 let serde_yaml::Value::Sequence(inc_seq) = inc else {
-                    return false;
+                    errors.push(ValidationError::type_mismatch(
+                        path, "array", inc,
+                    ));
+                    return;
                 };
 
                 if items.len() != inc_seq.len() {
-                    return false;
+                    let (exp, act) = (items.len(), inc_seq.len());
+                    errors.push(
+                        ValidationError::new(
+                            path,
+                            format!("expected {exp} items, found {act}"),
+                        )
+                        .expected(
+                            format!("{exp} items"),
+                            format!("{act} items"),
+                        ),
+                    );
+                    return;
+                }
+                for (i, (e, v)) in items.iter().zip(inc_seq.iter()).enumerate()
+                {
+                    e.check(v, vars, templates, &index_path(path, i), errors);
                 }
-                items
-                    .iter()
-                    .zip(inc_seq.iter())
-                    .all(|(e, i)| e.validate(i, vars, templates))
             }
             Body::Mapping(mapping) => {
                 let serde_yaml::Value::Mapping(inc_map) = inc else {
-                    return false;
+                    errors.push(ValidationError::type_mismatch(
+                        path, "object", inc,
+                    ));
+                    return;
                 };
 
                 for (k, exp_v) in mapping.map.iter() {
                     let key = k.resolve(vars, templates);
+                    let path = key_path(path, &key);
                     if let Some(inc_v) = inc_map.get(&key) {
-                        if !exp_v.validate(inc_v, vars, templates) {
-                            return false;
-                        }
-                    } else {
-                        return false;
+                        exp_v.check(inc_v, vars, templates, &path, errors);
+                    } else if !exp_v.is_optional() {
+                        errors.push(
+                            ValidationError::new(&path, "missing field")
+                                .expected(exp_v.type_name(), "missing"),
+                        );
                     }
                 }
-                true
             }
-            Body::Dynamic(dynamic) => dynamic.validate(inc, vars, templates),
+            Body::Dynamic(dynamic) => {
+                dynamic.check(inc, vars, templates, path, errors)
+            }
             Body::Constraint(constraint) => {
-                let same_type = match (constraint.exp_type.as_str(), inc) {
-                    ("string", serde_yaml::Value::String(_)) => true,
-                    ("number", serde_yaml::Value::Number(_)) => true,
-                    ("boolean", serde_yaml::Value::Bool(_)) => true,
-                    ("object", serde_yaml::Value::Mapping(_)) => true,
-                    ("array", serde_yaml::Value::Sequence(_)) => true,
-                    ("any", _) => true,
-                    _ => false,
-                };
-
-                if !same_type {
-                    return false;
-                }
-
-                if let Some(val) = &constraint.value {
-                    return val.validate(inc, vars, templates);
-                }
-                true
+                constraint.check(inc, vars, templates, path, errors)
             }
             _ => {
                 let resolved = self.resolve(vars, templates);
-                inc == &resolved
+                if inc != &resolved {
+                    errors.push(ValidationError::value_mismatch(
+                        path, &resolved, inc,
+                    ));
+                }
             }
         }
     }
 
+    /// Gets name of the type the body expects.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Body::Null => "null",
+            Body::Bool(_) => "boolean",
+            Body::Number(_) => "number",
+            Body::String(_) | Body::Dynamic(_) => "string",
+            Body::Sequence(_) => "array",
+            Body::Mapping(_) => "object",
+            Body::Tagged(tagged) => tagged.value.type_name(),
+            Body::Constraint(constraint) => &constraint.exp_type,
+        }
+    }
+
+    /// Checks whether the body can be missing in the incoming object.
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Body::Constraint(c) if c.optional)
+    }
+
     pub fn is_null(&self) -> bool {
         self == &Self::Null
     }
@@ -165,23 +210,24 @@ impl TryFrom<serde_yaml::Value> for Body {
                 }
                 Self::Sequence(vals)
             }
-            serde_yaml::Value::Mapping(map) => {
-                let is_constraint = map.len() <= 2
-                    && map.keys().all(|k| {
-                        k.as_str() == Some("type")
-                            || k.as_str() == Some("value")
-                    });
+            serde_yaml::Value::Mapping(mut map) => {
+                // Constraint with rules is marked by the `$type` key, mapping
+                // with only the `type` and `value` keys is a plain constraint.
+                let is_constraint = if let Some(typ) = map.remove(TYPE_MARKER)
+                {
+                    map.insert(str_value("type"), typ);
+                    true
+                } else {
+                    map.get(str_value("type")).is_some_and(|t| t.is_string())
+                        && map.keys().all(|k| {
+                            matches!(k.as_str(), Some("type" | "value"))
+                        })
+                };
                 if is_constraint {
-                    let type_val = map.get(&str_value("type")).unwrap();
-                    let typ = type_val.as_str().unwrap_or("any");
-
-                    let value = match map.get(&str_value("value")) {
-                        Some(v) => Some(Box::new(Self::try_from(v.clone())?)),
-                        None => None,
-                    };
-                    return Ok(Self::Constraint(TypeConstraint::new(
-                        typ, value,
-                    )));
+                    let constraint = serde_yaml::from_value(
+                        serde_yaml::Value::Mapping(map),
+                    )?;
+                    return Ok(Self::Constraint(constraint));
                 }
 
                 let mut new_map = Mapping::new();
@@ -224,6 +270,9 @@ impl TryFrom<String> for Body {
         if res.is_empty() {
             return Ok(Self::String(buffer));
         }
+        if !buffer.is_empty() {
+            res.push(DynamicValue::Static(buffer));
+        }
         Ok(Self::Dynamic(Dynamic::new(res)))
     }
 }
@@ -257,6 +306,11 @@ impl Body {
         match ident.as_str() {
             "fake" => Ok((ident, DynamicValue::Fake(attr))),
             "ref" => Ok((ident, DynamicValue::Ref(attr))),
+            "env" => Ok((ident, DynamicValue::Env(attr))),
+            "jwt" => {
+                let var = DynamicValue::Var(format!("{ident}.{attr}"));
+                Ok((ident, var))
+            }
             _ => Err(UrlError::UnknownObject(ident).into()),
         }
     }
@@ -279,7 +333,7 @@ impl Body {
             ident.push(*c);
             chars.next();
         }
-        return Ok(ident);
+        Ok(ident)
     }
 }
 
@@ -319,15 +373,7 @@ impl From<&Body> for serde_yaml::Value {
             Body::Dynamic(dynamic) => {
                 serde_yaml::Value::String(dynamic.to_string())
             }
-            Body::Constraint(constraint) => {
-                let mut map = serde_yaml::Mapping::new();
-                map.insert(str_value("type"), str_value(&constraint.exp_type));
-                if let Some(val) = &constraint.value {
-                    map.insert(str_value("value"), Self::from(val.as_ref()));
-                }
-
-                serde_yaml::Value::Mapping(map)
-            }
+            Body::Constraint(constraint) => constraint.to_value(),
         }
     }
 }
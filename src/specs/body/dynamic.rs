@@ -6,7 +6,10 @@ use serde_yaml::Value;
 
 use crate::{
     server::url::var::UrlVar,
-    specs::body::{body::Body, fake::get_fake},
+    specs::{
+        body::{body::Body, fake::get_fake},
+        validation::ValidationError,
+    },
 };
 
 #[derive(Debug, PartialEq, PartialOrd, Clone, Hash)]
@@ -20,6 +23,8 @@ pub enum DynamicValue {
     Var(String),
     Fake(String),
     Ref(String),
+    /// Environment variable read when resolving.
+    Env(String),
 }
 
 impl Dynamic {
@@ -39,6 +44,7 @@ impl Dynamic {
             return match var {
                 UrlVar::String(s) => Value::String(s.clone()),
                 UrlVar::Number(n) => Value::Number((*n).into()),
+                UrlVar::Value(v) => v.clone(),
             };
         }
 
@@ -60,6 +66,12 @@ impl Dynamic {
                         warn!("Response variable `$fake.{attr}` not defined.");
                     }
                 }
+                DynamicValue::Env(name) => match std::env::var(name) {
+                    Ok(val) => res.push_str(&val),
+                    Err(_) => {
+                        warn!("Environment variable `$env.{name}` not set.")
+                    }
+                },
                 DynamicValue::Ref(ref_name) => {
                     if let Some(body) = templates.get(ref_name) {
                         let resolved = body.resolve(vars, templates);
@@ -86,19 +98,40 @@ impl Dynamic {
         vars: &HashMap<String, UrlVar>,
         templates: &HashMap<String, Body>,
     ) -> bool {
+        let mut errors = vec![];
+        self.check(inc, vars, templates, "$", &mut errors);
+        errors.is_empty()
+    }
+
+    /// Checks the incoming value on the given JSON `path` and collects the
+    /// mismatches into `errors`.
+    pub fn check(
+        &self,
+        inc: &serde_yaml::Value,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
         if self.values.len() == 1
             && let DynamicValue::Ref(ref_name) = &self.values[0]
         {
             if let Some(template) = templates.get(ref_name) {
-                return template.validate(inc, vars, templates);
+                template.check(inc, vars, templates, path, errors);
             } else {
                 warn!("Template `$ref.{ref_name} not defined.");
-                return false;
+                errors.push(ValidationError::new(
+                    path,
+                    format!("template `{ref_name}` not defined"),
+                ));
             }
+            return;
         }
 
         let resolved = self.resolve(vars, templates);
-        inc == &resolved
+        if inc != &resolved {
+            errors.push(ValidationError::value_mismatch(path, &resolved, inc));
+        }
     }
 }
 
@@ -109,6 +142,7 @@ impl Display for Dynamic {
                 DynamicValue::Static(s) => write!(f, "{s}")?,
                 DynamicValue::Var(ident) => write!(f, "${{{ident}}}")?,
                 DynamicValue::Fake(attr) => write!(f, "${{fake.{attr}}}")?,
+                DynamicValue::Env(name) => write!(f, "${{env.{name}}}")?,
                 DynamicValue::Ref(ref_name) => {
                     write!(f, "${{ref.{ref_name}}}")?
                 }
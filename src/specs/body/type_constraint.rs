@@ -1,12 +1,72 @@
-use crate::specs::body::body::Body;
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    server::url::var::UrlVar,
+    specs::{
+        body::body::Body,
+        pattern::Pattern,
+        validation::{
+            ValidationError, describe, index_path, key_path, type_name,
+        },
+    },
+};
+
+/// Key marking the mapping as type constraint with validation rules.
+pub const TYPE_MARKER: &str = "$type";
 
 /// Adds type constraint to the Body
 ///
 /// It allows easier handling of type constraint values.
-#[derive(Debug, PartialEq, Clone, Hash)]
+#[derive(Debug, PartialEq, Clone, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TypeConstraint {
+    #[serde(rename = "type")]
     pub exp_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub value: Option<Box<Body>>,
+    /// Whether the field can be missing in the incoming object.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<serde_yaml::Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<serde_yaml::Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// List of the allowed values.
+    #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub one_of: Vec<Body>,
+    /// Constraint of each of the array items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Body>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Whether the object can contain fields not listed in the `value`.
+    #[serde(
+        default = "default_additional_properties",
+        skip_serializing_if = "is_true"
+    )]
+    pub additional_properties: bool,
+}
+
+/// Well known format of the string value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    Email,
+    Uuid,
+    DateTime,
 }
 
 impl TypeConstraint {
@@ -18,6 +78,270 @@ impl TypeConstraint {
         Self {
             exp_type: typ.to_string(),
             value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Converts the constraint to the mapping it's written as. The `type`
+    /// key is written as `$type` when the constraint has any rules besides
+    /// the `value`.
+    pub fn to_value(&self) -> serde_yaml::Value {
+        let mut value = serde_yaml::to_value(self).unwrap_or_default();
+        if let serde_yaml::Value::Mapping(map) = &mut value
+            && map
+                .keys()
+                .any(|k| !matches!(k.as_str(), Some("type" | "value")))
+            && let Some(typ) = map.remove("type")
+        {
+            map.insert(TYPE_MARKER.into(), typ);
+        }
+        value
+    }
+
+    /// Checks the incoming value on the given JSON `path` against the
+    /// constraint and collects all the found mismatches into `errors`.
+    pub fn check(
+        &self,
+        inc: &serde_yaml::Value,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let same_type = matches!(
+            (self.exp_type.as_str(), inc),
+            ("string", serde_yaml::Value::String(_))
+                | ("number", serde_yaml::Value::Number(_))
+                | ("boolean", serde_yaml::Value::Bool(_))
+                | ("object", serde_yaml::Value::Mapping(_))
+                | ("array", serde_yaml::Value::Sequence(_))
+                | ("any", _)
+        );
+        if !same_type {
+            errors.push(ValidationError::type_mismatch(
+                path,
+                &self.exp_type,
+                inc,
+            ));
+            return;
+        }
+
+        match inc {
+            serde_yaml::Value::Number(num) => {
+                self.check_number(num, path, errors)
+            }
+            serde_yaml::Value::String(s) => self.check_string(s, path, errors),
+            serde_yaml::Value::Sequence(seq) => {
+                self.check_items(seq, vars, templates, path, errors)
+            }
+            serde_yaml::Value::Mapping(map) => {
+                self.check_fields(map, vars, templates, path, errors)
+            }
+            _ => {}
+        }
+
+        if !self.one_of.is_empty() {
+            let allowed: Vec<_> = self
+                .one_of
+                .iter()
+                .map(|v| v.resolve(vars, templates))
+                .collect();
+            if !allowed.contains(inc) {
+                let expected = serde_yaml::Value::Sequence(allowed);
+                errors.push(
+                    ValidationError::new(path, "value not in enum").expected(
+                        format!("one of {}", describe(&expected)),
+                        describe(inc),
+                    ),
+                );
+            }
+        }
+
+        if let Some(val) = &self.value {
+            val.check(inc, vars, templates, path, errors);
+        }
+    }
+
+    fn check_number(
+        &self,
+        num: &serde_yaml::Number,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let Some(val) = num.as_f64() else {
+            return;
+        };
+        if let Some(min) = &self.min
+            && min.as_f64().is_some_and(|min| val < min)
+        {
+            errors.push(
+                ValidationError::new(path, format!("expected at least {min}"))
+                    .expected(format!(">= {min}"), num.to_string()),
+            );
+        }
+        if let Some(max) = &self.max
+            && max.as_f64().is_some_and(|max| val > max)
+        {
+            errors.push(
+                ValidationError::new(path, format!("expected at most {max}"))
+                    .expected(format!("<= {max}"), num.to_string()),
+            );
+        }
+    }
+
+    fn check_string(
+        &self,
+        s: &str,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let len = s.chars().count();
+        if let Some(min) = self.min_length
+            && len < min
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("expected at least {min} characters"),
+                )
+                .expected(format!(">= {min} characters"), len_str(len)),
+            );
+        }
+        if let Some(max) = self.max_length
+            && len > max
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("expected at most {max} characters"),
+                )
+                .expected(format!("<= {max} characters"), len_str(len)),
+            );
+        }
+        if let Some(pattern) = &self.pattern
+            && !pattern.0.is_match(s)
+        {
+            errors.push(
+                ValidationError::new(path, "value does not match pattern")
+                    .expected(pattern.0.as_str(), s),
+            );
+        }
+        if let Some(format) = self.format
+            && !format.matches(s)
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("expected {} format", format.name()),
+                )
+                .expected(format.name(), s),
+            );
+        }
+    }
+
+    fn check_items(
+        &self,
+        seq: &serde_yaml::Sequence,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let len = seq.len();
+        if let Some(min) = self.min_items
+            && len < min
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("expected at least {min} items"),
+                )
+                .expected(format!(">= {min} items"), format!("{len} items")),
+            );
+        }
+        if let Some(max) = self.max_items
+            && len > max
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("expected at most {max} items"),
+                )
+                .expected(format!("<= {max} items"), format!("{len} items")),
+            );
+        }
+        if let Some(items) = &self.items {
+            for (i, item) in seq.iter().enumerate() {
+                items.check(
+                    item,
+                    vars,
+                    templates,
+                    &index_path(path, i),
+                    errors,
+                );
+            }
+        }
+    }
+
+    fn check_fields(
+        &self,
+        map: &serde_yaml::Mapping,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if self.additional_properties {
+            return;
+        }
+
+        let known: Vec<_> = match self.value.as_deref() {
+            Some(Body::Mapping(mapping)) => mapping
+                .map
+                .keys()
+                .map(|k| k.resolve(vars, templates))
+                .collect(),
+            _ => vec![],
+        };
+        for key in map.keys().filter(|k| !known.contains(k)) {
+            errors.push(
+                ValidationError::new(&key_path(path, key), "unexpected field")
+                    .expected("missing", type_name(&map[key])),
+            );
+        }
+    }
+}
+
+impl Format {
+    /// Checks whether the string is in the format.
+    pub fn matches(&self, s: &str) -> bool {
+        static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap()
+        });
+        static UUID: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}$")
+                .unwrap()
+        });
+        static DATE_TIME: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(
+                r"^\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$",
+            )
+            .unwrap()
+        });
+
+        match self {
+            Format::Email => EMAIL.is_match(s),
+            Format::Uuid => UUID.is_match(s),
+            Format::DateTime => DATE_TIME.is_match(s),
+        }
+    }
+
+    /// Gets the format name as used in the specification.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Email => "email",
+            Format::Uuid => "uuid",
+            Format::DateTime => "date-time",
         }
     }
 }
@@ -27,6 +351,30 @@ impl Default for TypeConstraint {
         Self {
             exp_type: "any".to_string(),
             value: Default::default(),
+            optional: false,
+            min: None,
+            max: None,
+            min_length: None,
+            max_length: None,
+            pattern: None,
+            format: None,
+            one_of: vec![],
+            items: None,
+            min_items: None,
+            max_items: None,
+            additional_properties: true,
         }
     }
 }
+
+fn default_additional_properties() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn len_str(len: usize) -> String {
+    format!("{len} characters")
+}
@@ -0,0 +1,214 @@
+use hyper::{
+    HeaderMap, StatusCode,
+    header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+        ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+        HeaderValue, ORIGIN, VARY,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    server::{HyperRes, full_body},
+    specs::pattern::Pattern,
+};
+
+/// Methods allowed when the policy doesn't list any.
+const DEFAULT_METHODS: &[&str] =
+    &["GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD"];
+
+/// Cross-Origin Resource Sharing policy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cors {
+    /// Allowed origins, defaults to any origin unless `origin_pattern` is
+    /// set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origins: Option<Origins>,
+    /// Regular expression of the allowed origins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_pattern: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_credentials: bool,
+    /// Allowed methods, defaults to the common HTTP methods.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_methods: Vec<String>,
+    /// Allowed request headers, defaults to the requested headers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_headers: Vec<String>,
+    /// Response headers exposed to the browser scripts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expose_headers: Vec<String>,
+    /// Number of seconds the preflight response can be cached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u64>,
+}
+
+/// Allowed origins of the CORS policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Origins {
+    Keyword(OriginKeyword),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OriginKeyword {
+    /// Any origin is allowed.
+    #[serde(rename = "*")]
+    Any,
+    /// Origin of the request is allowed.
+    #[serde(rename = "reflect")]
+    Reflect,
+}
+
+impl Cors {
+    /// Checks whether the request is CORS preflight request.
+    pub fn is_preflight(method: &hyper::Method, headers: &HeaderMap) -> bool {
+        method == hyper::Method::OPTIONS
+            && headers.contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+    }
+
+    /// Creates response to the CORS preflight request. Requests with
+    /// disallowed origin, method or headers are rejected.
+    pub fn preflight(&self, req: &HeaderMap) -> HyperRes {
+        let method = header_str(req, ACCESS_CONTROL_REQUEST_METHOD);
+        let requested: Vec<_> =
+            header_str(req, ACCESS_CONTROL_REQUEST_HEADERS)
+                .split(',')
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+                .collect();
+
+        let origin = req.get(ORIGIN).and_then(|o| o.to_str().ok());
+        let Some(origin) = self.allow_origin(origin) else {
+            return Self::rejected("origin not allowed");
+        };
+        if !self
+            .methods()
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(method))
+        {
+            return Self::rejected("method not allowed");
+        }
+        if !self.allow_headers.is_empty()
+            && !requested.iter().all(|h| {
+                self.allow_headers.iter().any(|a| a.eq_ignore_ascii_case(h))
+            })
+        {
+            return Self::rejected("headers not allowed");
+        }
+
+        let mut res = hyper::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(full_body(""))
+            .unwrap();
+        let headers = res.headers_mut();
+        self.insert_origin(headers, origin);
+        let methods = self.methods().into_iter();
+        insert(headers, ACCESS_CONTROL_ALLOW_METHODS, methods);
+        if self.allow_headers.is_empty() {
+            insert(
+                headers,
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                requested.into_iter(),
+            );
+        } else {
+            let allowed = self.allow_headers.iter().map(String::as_str);
+            insert(headers, ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+        }
+        if let Some(max_age) = self.max_age {
+            headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.into());
+        }
+        res
+    }
+
+    /// Adds the CORS headers to the response of the request from the given
+    /// `origin`.
+    pub fn inject(&self, origin: Option<&str>, res: &mut HeaderMap) {
+        let Some(origin) = self.allow_origin(origin) else {
+            return;
+        };
+        self.insert_origin(res, origin);
+        let exposed = self.expose_headers.iter().map(String::as_str);
+        insert(res, ACCESS_CONTROL_EXPOSE_HEADERS, exposed);
+    }
+
+    /// Gets the value of the allowed origin header for the request, `None`
+    /// when the request origin is not allowed.
+    fn allow_origin(&self, origin: Option<&str>) -> Option<String> {
+        let pattern = self.origin_pattern.as_ref();
+        let keyword = match &self.origins {
+            None if pattern.is_none() => OriginKeyword::Any,
+            Some(Origins::Keyword(keyword)) => *keyword,
+            origins => {
+                let origin = origin?;
+                let listed = matches!(
+                    origins,
+                    Some(Origins::List(list)) if list.iter().any(|o| o == origin)
+                );
+                let matched = pattern.is_some_and(|p| p.0.is_match(origin));
+                return (listed || matched).then(|| origin.to_owned());
+            }
+        };
+
+        match keyword {
+            // Wildcard is not allowed for credentialed requests.
+            OriginKeyword::Any if !self.allow_credentials => Some("*".into()),
+            _ => origin.map(str::to_owned),
+        }
+    }
+
+    fn insert_origin(&self, headers: &mut HeaderMap, origin: String) {
+        if origin != "*" {
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+        }
+        if let Ok(origin) = HeaderValue::try_from(origin) {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        }
+        if self.allow_credentials {
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+
+    fn methods(&self) -> Vec<&str> {
+        if self.allow_methods.is_empty() {
+            DEFAULT_METHODS.to_vec()
+        } else {
+            self.allow_methods.iter().map(String::as_str).collect()
+        }
+    }
+
+    fn rejected(reason: &str) -> HyperRes {
+        hyper::Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(full_body(format!("CORS preflight rejected: {reason}")))
+            .unwrap()
+    }
+}
+
+/// Inserts the comma separated values as the header, if there are any.
+fn insert<'a>(
+    headers: &mut HeaderMap,
+    name: hyper::header::HeaderName,
+    values: impl Iterator<Item = &'a str>,
+) {
+    let value = values.collect::<Vec<_>>().join(", ");
+    if !value.is_empty()
+        && let Ok(value) = HeaderValue::try_from(value)
+    {
+        headers.insert(name, value);
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: hyper::header::HeaderName) -> &str {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
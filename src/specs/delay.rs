@@ -0,0 +1,120 @@
+use std::{str::FromStr, time::Duration};
+
+use fake::rand::{self, Rng};
+use pareg::{ArgError, FromArgStr};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Response delay in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Delay {
+    /// Constant delay.
+    Fixed(u64),
+    /// Uniformly distributed delay in the given range (inclusive).
+    Range { min: u64, max: u64 },
+    /// Delay with the given distribution, mean and standard deviation.
+    Random {
+        distribution: Distribution,
+        mean: f64,
+        stddev: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    Normal,
+    LogNormal,
+}
+
+impl Delay {
+    /// Checks that the distribution parameters are valid, lognormal
+    /// distribution requires positive mean.
+    pub fn validate(&self) -> Result<()> {
+        let Delay::Random {
+            distribution,
+            mean,
+            stddev,
+        } = self
+        else {
+            return Ok(());
+        };
+        if *distribution == Distribution::LogNormal
+            && (*mean <= 0.0 || mean.is_nan())
+        {
+            return Err(Error::Msg(format!(
+                "The lognormal delay mean {mean} must be greater than 0"
+            )));
+        }
+        if *stddev < 0.0 || stddev.is_nan() {
+            return Err(Error::Msg(format!(
+                "The delay standard deviation {stddev} must not be negative"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Gets the delay duration, sampled from the delay distribution.
+    pub fn sample(&self) -> Duration {
+        let ms = match self {
+            Delay::Fixed(ms) => *ms as f64,
+            Delay::Range { min, max } if min >= max => *min as f64,
+            Delay::Range { min, max } => {
+                rand::rng().random_range(*min..=*max) as f64
+            }
+            Delay::Random {
+                distribution: Distribution::Normal,
+                mean,
+                stddev,
+            } => mean + stddev * std_normal(),
+            Delay::Random {
+                distribution: Distribution::LogNormal,
+                mean,
+                stddev,
+            } => {
+                // Parameters of the underlying normal distribution, so the
+                // result has the given mean and standard deviation.
+                let var = (1.0 + (stddev * stddev) / (mean * mean)).ln();
+                let mu = mean.ln() - var / 2.0;
+                (mu + var.sqrt() * std_normal()).exp()
+            }
+        };
+        Duration::from_millis(ms.max(0.0).round() as u64)
+    }
+}
+
+/// Parses delay from the string, either `<ms>` or `<min>..<max>`.
+impl FromStr for Delay {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |v: &str| {
+            v.trim().parse::<u64>().map_err(|_| {
+                ArgError::parse_msg(
+                    "expected delay `<ms>` or `<min>..<max>`",
+                    s.to_owned(),
+                )
+            })
+        };
+
+        match s.split_once("..") {
+            Some((min, max)) => Ok(Delay::Range {
+                min: parse(min)?,
+                max: parse(max)?,
+            }),
+            None => Ok(Delay::Fixed(parse(s)?)),
+        }
+    }
+}
+
+impl FromArgStr for Delay {}
+
+/// Samples standard normal distribution using the Box-Muller transform.
+fn std_normal() -> f64 {
+    let mut rng = rand::rng();
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    specs::{mount::has_prefix, response::Response},
+};
+
+/// Response to the unmatched requests with URL under the given prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fallback {
+    /// URL prefix, matched by whole path segments. Trailing `/**` glob is
+    /// allowed.
+    pub prefix: String,
+    pub response: Response,
+}
+
+impl Fallback {
+    /// Strips the trailing `/**` glob and slashes from the prefix, and
+    /// rejects the prefixes with other wildcards.
+    pub fn normalize(&mut self) -> Result<()> {
+        let prefix = self.prefix.strip_suffix("/**").unwrap_or(&self.prefix);
+        if prefix.contains('*') {
+            return Err(Error::Msg(format!(
+                "Invalid fallback prefix '{}'. Only trailing '/**' wildcard \
+                is supported.",
+                self.prefix
+            )));
+        }
+        let prefix = prefix.trim_end_matches('/');
+        self.prefix = if prefix.is_empty() { "/" } else { prefix }.to_owned();
+        Ok(())
+    }
+
+    /// Checks whether the URL path is under the fallback prefix.
+    pub fn matches(&self, path: &str) -> bool {
+        has_prefix(path, &self.prefix)
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    server::url::var::UrlVar,
+    specs::body::body::Body,
+};
+
+pub mod schema;
+
+pub use schema::Schema;
+
+/// GraphQL endpoint specification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraphQl {
+    /// Path to the SDL schema used to fake operations without mock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<GraphQlOperation>,
+
+    #[serde(skip)]
+    pub sdl: Option<Arc<Schema>>,
+}
+
+/// Mocked result of the matching GraphQL operations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraphQlOperation {
+    /// Expected operation name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// Root field the operation has to select.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Expected operation variables (the operation may contain more).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Body>,
+    #[serde(default, skip_serializing_if = "Body::is_null")]
+    pub data: Body,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Body>,
+}
+
+impl GraphQl {
+    /// Loads the SDL schema, relative paths are resolved against `dir`.
+    pub fn load_schema(&mut self, dir: &Path) -> Result<()> {
+        let Some(path) = &self.schema else {
+            return Ok(());
+        };
+        let path = dir.join(path);
+        let sdl = std::fs::read_to_string(&path).map_err(|e| {
+            Error::Msg(format!(
+                "Reading GraphQL schema '{}': {e}",
+                path.display()
+            ))
+        })?;
+        self.sdl = Some(Arc::new(Schema::parse(&sdl)?));
+        Ok(())
+    }
+
+    /// Finds the first operation mock matching the operation `name`, its
+    /// root `fields` and `variables`.
+    pub fn find(
+        &self,
+        name: Option<&str>,
+        fields: &[&str],
+        variables: &serde_yaml::Value,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Option<&GraphQlOperation> {
+        self.operations.iter().find(|op| {
+            op.operation.as_deref().is_none_or(|o| Some(o) == name)
+                && op.field.as_deref().is_none_or(|f| fields.contains(&f))
+                && op
+                    .variables
+                    .as_ref()
+                    .is_none_or(|v| v.validate(variables, vars, templates))
+        })
+    }
+}
+
+impl GraphQlOperation {
+    /// Resolves the GraphQL response containing the `data` and `errors`.
+    pub fn resolve(
+        &self,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> serde_yaml::Value {
+        let mut res = serde_yaml::Mapping::new();
+        res.insert("data".into(), self.data.resolve(vars, templates));
+        if let Some(errors) = &self.errors {
+            res.insert("errors".into(), errors.resolve(vars, templates));
+        }
+        serde_yaml::Value::Mapping(res)
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+
+use fake::{
+    Fake,
+    faker::lorem::en::Words,
+    locales::EN,
+    rand::{self, Rng, seq::IndexedRandom},
+};
+use graphql_parser::{
+    query::{FragmentDefinition, Selection, SelectionSet},
+    schema::{self, Definition, TypeDefinition, TypeExtension},
+};
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    error::{Error, Result},
+    specs::body::fake::get_fake,
+};
+
+type Type = schema::Type<'static, String>;
+pub type Fragments<'a> = HashMap<&'a str, &'a FragmentDefinition<'a, String>>;
+
+/// Types of the GraphQL schema, used to fake operation results.
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub query: String,
+    pub mutation: String,
+    pub subscription: String,
+    types: HashMap<String, SchemaType>,
+}
+
+#[derive(Debug)]
+enum SchemaType {
+    Object {
+        fields: HashMap<String, Type>,
+        interfaces: Vec<String>,
+    },
+    Interface {
+        fields: HashMap<String, Type>,
+    },
+    Union(Vec<String>),
+    Enum(Vec<String>),
+    Scalar,
+}
+
+impl Schema {
+    /// Parses the schema from the SDL.
+    pub fn parse(sdl: &str) -> Result<Self> {
+        let doc = schema::parse_schema::<String>(sdl)
+            .map_err(|e| Error::Msg(format!("Invalid GraphQL schema: {e}")))?
+            .into_static();
+
+        let mut schema = Schema {
+            query: "Query".into(),
+            mutation: "Mutation".into(),
+            subscription: "Subscription".into(),
+            types: HashMap::new(),
+        };
+        for def in doc.definitions {
+            match def {
+                Definition::SchemaDefinition(def) => {
+                    let roots = [
+                        (&mut schema.query, def.query),
+                        (&mut schema.mutation, def.mutation),
+                        (&mut schema.subscription, def.subscription),
+                    ];
+                    for (root, name) in roots {
+                        if let Some(name) = name {
+                            *root = name;
+                        }
+                    }
+                }
+                Definition::TypeDefinition(def) => schema.add_type(def),
+                Definition::TypeExtension(TypeExtension::Object(ext)) => {
+                    if let Some(SchemaType::Object { fields, .. }) =
+                        schema.types.get_mut(&ext.name)
+                    {
+                        fields.extend(
+                            ext.fields
+                                .into_iter()
+                                .map(|f| (f.name, f.field_type)),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Fakes the result of the selection on the given type.
+    pub fn fake<'a>(
+        &self,
+        type_name: &str,
+        set: &'a SelectionSet<'a, String>,
+        fragments: &Fragments<'a>,
+    ) -> Value {
+        self.fake_object(type_name, set, fragments, &mut HashSet::new())
+    }
+
+    /// Fakes the selection on the object type. The `visited` fragments are
+    /// the fragments being expanded, so that cyclic fragment isn't expanded
+    /// again.
+    fn fake_object<'a>(
+        &self,
+        type_name: &str,
+        set: &'a SelectionSet<'a, String>,
+        fragments: &Fragments<'a>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Value {
+        let mut map = Mapping::new();
+        self.collect(type_name, set, fragments, visited, &mut map);
+        Value::Mapping(map)
+    }
+
+    fn add_type(&mut self, def: TypeDefinition<'static, String>) {
+        let fields = |fields: Vec<schema::Field<'static, String>>| {
+            fields.into_iter().map(|f| (f.name, f.field_type)).collect()
+        };
+        let (name, ty) = match def {
+            TypeDefinition::Object(obj) => (
+                obj.name,
+                SchemaType::Object {
+                    fields: fields(obj.fields),
+                    interfaces: obj.implements_interfaces,
+                },
+            ),
+            TypeDefinition::Interface(int) => (
+                int.name,
+                SchemaType::Interface {
+                    fields: fields(int.fields),
+                },
+            ),
+            TypeDefinition::Union(union) => {
+                (union.name, SchemaType::Union(union.types))
+            }
+            TypeDefinition::Enum(en) => (
+                en.name,
+                SchemaType::Enum(
+                    en.values.into_iter().map(|v| v.name).collect(),
+                ),
+            ),
+            TypeDefinition::Scalar(scalar) => {
+                (scalar.name, SchemaType::Scalar)
+            }
+            TypeDefinition::InputObject(_) => return,
+        };
+        self.types.insert(name, ty);
+    }
+
+    /// Fakes the selected fields into the `map`, including the fields of the
+    /// fragments applying to the type.
+    fn collect<'a>(
+        &self,
+        type_name: &str,
+        set: &'a SelectionSet<'a, String>,
+        fragments: &Fragments<'a>,
+        visited: &mut HashSet<&'a str>,
+        map: &mut Mapping,
+    ) {
+        for item in &set.items {
+            match item {
+                Selection::Field(field) => {
+                    let key = field.alias.as_ref().unwrap_or(&field.name);
+                    let value = if field.name == "__typename" {
+                        Value::String(type_name.to_owned())
+                    } else if let Some(ty) = self.field(type_name, &field.name)
+                    {
+                        self.fake_type(
+                            ty,
+                            &field.name,
+                            &field.selection_set,
+                            fragments,
+                            visited,
+                        )
+                    } else {
+                        Value::Null
+                    };
+                    map.insert(key.as_str().into(), value);
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_str();
+                    let Some(frag) = fragments.get(name) else {
+                        continue;
+                    };
+                    let graphql_parser::query::TypeCondition::On(cond) =
+                        &frag.type_condition;
+                    if self.applies(type_name, cond) && visited.insert(name) {
+                        self.collect(
+                            type_name,
+                            &frag.selection_set,
+                            fragments,
+                            visited,
+                            map,
+                        );
+                        visited.remove(name);
+                    }
+                }
+                Selection::InlineFragment(frag) => {
+                    let applies = match &frag.type_condition {
+                        Some(graphql_parser::query::TypeCondition::On(
+                            cond,
+                        )) => self.applies(type_name, cond),
+                        None => true,
+                    };
+                    if applies {
+                        self.collect(
+                            type_name,
+                            &frag.selection_set,
+                            fragments,
+                            visited,
+                            map,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn fake_type<'a>(
+        &self,
+        ty: &Type,
+        field: &str,
+        set: &'a SelectionSet<'a, String>,
+        fragments: &Fragments<'a>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Value {
+        match ty {
+            Type::NonNullType(inner) => {
+                self.fake_type(inner, field, set, fragments, visited)
+            }
+            Type::ListType(inner) => {
+                let len = rand::rng().random_range(1..=3);
+                (0..len)
+                    .map(|_| {
+                        self.fake_type(inner, field, set, fragments, visited)
+                    })
+                    .collect()
+            }
+            Type::NamedType(name) => match self.types.get(name) {
+                Some(SchemaType::Object { .. }) => {
+                    self.fake_object(name, set, fragments, visited)
+                }
+                Some(SchemaType::Interface { .. } | SchemaType::Union(_)) => {
+                    let concrete = self.concrete(name);
+                    self.fake_object(concrete, set, fragments, visited)
+                }
+                Some(SchemaType::Enum(values)) => values
+                    .choose(&mut rand::rng())
+                    .map(|v| Value::String(v.clone()))
+                    .unwrap_or_default(),
+                Some(SchemaType::Scalar) | None => fake_scalar(name, field),
+            },
+        }
+    }
+
+    /// Gets the type of the field of the given object or interface.
+    fn field(&self, type_name: &str, field: &str) -> Option<&Type> {
+        match self.types.get(type_name)? {
+            SchemaType::Object { fields, .. }
+            | SchemaType::Interface { fields } => fields.get(field),
+            _ => None,
+        }
+    }
+
+    /// Checks whether fragment with type condition `cond` applies to the
+    /// type.
+    fn applies(&self, type_name: &str, cond: &str) -> bool {
+        if type_name == cond {
+            return true;
+        }
+        match (self.types.get(type_name), self.types.get(cond)) {
+            (Some(SchemaType::Object { interfaces, .. }), _)
+                if interfaces.iter().any(|i| i == cond) =>
+            {
+                true
+            }
+            (_, Some(SchemaType::Union(types))) => {
+                types.iter().any(|t| t == type_name)
+            }
+            _ => false,
+        }
+    }
+
+    /// Picks random object type of the interface or union.
+    fn concrete<'a>(&'a self, name: &'a str) -> &'a str {
+        let candidates: Vec<&str> = match self.types.get(name) {
+            Some(SchemaType::Union(types)) => {
+                types.iter().map(|t| t.as_str()).collect()
+            }
+            _ => self
+                .types
+                .iter()
+                .filter(|(_, ty)| {
+                    matches!(ty, SchemaType::Object { interfaces, .. }
+                        if interfaces.iter().any(|i| i == name))
+                })
+                .map(|(n, _)| n.as_str())
+                .collect(),
+        };
+        candidates.choose(&mut rand::rng()).copied().unwrap_or(name)
+    }
+}
+
+/// Fakes value of the scalar type. Strings are faked based on the field name
+/// when it matches a `fake` object attribute.
+fn fake_scalar(ty: &str, field: &str) -> Value {
+    let mut rng = rand::rng();
+    match ty {
+        "Int" => Value::Number(rng.random_range(0..1000).into()),
+        "Float" => {
+            let num: f64 = rng.random_range(0.0..1000.0);
+            Value::Number(((num * 100.0).round() / 100.0).into())
+        }
+        "Boolean" => Value::Bool(rng.random()),
+        "ID" => Value::String(rng.random::<u32>().to_string()),
+        _ => Value::String(fake_string(field)),
+    }
+}
+
+fn fake_string(field: &str) -> String {
+    let mut attr = String::new();
+    for c in field.chars() {
+        if c.is_uppercase() && !attr.is_empty() {
+            attr.push('_');
+        }
+        attr.push(c.to_ascii_lowercase());
+    }
+
+    let alias = match attr.rsplit('_').next().unwrap_or_default() {
+        "email" => "safe_email",
+        "phone" => "phone_number",
+        "city" => "city_name",
+        "street" => "street_name",
+        "company" => "company_name",
+        "country" => "country_code",
+        _ => "",
+    };
+    get_fake(&attr, EN)
+        .or_else(|| get_fake(alias, EN))
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| Words(1..4).fake::<Vec<String>>().join(" "))
+}
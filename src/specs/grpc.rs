@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use prost_reflect::MethodDescriptor;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    server::url::var::UrlVar,
+    specs::{body::body::Body, response::EndpointResponse},
+};
+
+/// gRPC endpoint specification. The endpoint URL is the gRPC method path
+/// (`/<package>.<Service>/<Method>`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Grpc {
+    /// Path to the `.proto` file containing the service.
+    pub proto: PathBuf,
+    /// Additional directories searched for the imported `.proto` files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<PathBuf>,
+    /// Responses to the requests matching the given fields.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cases: Vec<GrpcCase>,
+
+    #[serde(skip)]
+    pub method: Option<MethodDescriptor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrpcCase {
+    pub request: Body,
+    pub response: EndpointResponse,
+}
+
+impl Grpc {
+    /// Compiles the `.proto` file and finds the method given by the `url`.
+    /// Relative paths are resolved against `dir`.
+    pub fn load(&mut self, dir: &Path, url: &str) -> Result<()> {
+        let proto = dir.join(&self.proto);
+        let proto_dir = proto.parent().unwrap_or(dir).to_owned();
+        let includes = std::iter::once(proto_dir)
+            .chain(self.includes.iter().map(|i| dir.join(i)));
+
+        let pool = protox::Compiler::new(includes)
+            .and_then(|mut c| c.open_file(&proto).map(|c| c.descriptor_pool()))
+            .map_err(|e| Error::Msg(format!("Compiling proto: {e}")))?;
+
+        let (service, method) =
+            url.trim_start_matches('/').split_once('/').ok_or_else(|| {
+                Error::Msg(format!(
+                    "Invalid gRPC URL '{url}', expected \
+                    '/<package>.<Service>/<Method>'"
+                ))
+            })?;
+        let method = pool
+            .get_service_by_name(service)
+            .and_then(|s| s.methods().find(|m| m.name() == method))
+            .ok_or_else(|| {
+                Error::Msg(format!("Unknown gRPC method '{service}/{method}'"))
+            })?;
+        if method.is_client_streaming() || method.is_server_streaming() {
+            return Err(Error::Msg(format!(
+                "Streaming gRPC method '{service}/{}' is not supported",
+                method.name()
+            )));
+        }
+
+        self.method = Some(method);
+        Ok(())
+    }
+
+    /// Finds response of the first case matching the request message.
+    pub fn find(
+        &self,
+        request: &serde_yaml::Value,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Option<&EndpointResponse> {
+        self.cases
+            .iter()
+            .find(|c| c.request.validate(request, vars, templates))
+            .map(|c| &c.response)
+    }
+}
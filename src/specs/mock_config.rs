@@ -1,37 +1,117 @@
 use std::{
     collections::HashMap,
-    fs::File,
-    io::{BufReader, BufWriter},
+    fs::{self, File},
+    io::BufWriter,
     path::Path,
+    sync::LazyLock,
 };
 
+use indexmap::IndexMap;
 use oas3::OpenApiV3Spec;
+use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     args::import::Import,
     error::{Error, Result},
-    specs::{body::body::Body, spec::Spec},
+    specs::{
+        auth::Auth,
+        body::body::Body,
+        cors::Cors,
+        fallback::Fallback,
+        mount::{Include, Mount},
+        oidc::OidcConfig,
+        rate_limit::RateLimit,
+        response::Response,
+        spec::Spec,
+    },
 };
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct MockConfig {
+    /// Other specification files, directories or globs to load, relative to
+    /// this file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<Include>,
+    /// URL prefix and defaults of the endpoints in this file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount: Option<Mount>,
     #[serde(default)]
     pub templates: HashMap<String, Body>,
     #[serde(default)]
     pub specs: Vec<Spec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oidc: Option<OidcConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors: Option<Cors>,
+    /// Default response to the requests failing the validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_invalid: Option<Response>,
+    /// Response to the requests not matching any endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_found: Option<Response>,
+    /// Responses to the unmatched requests under the given URL prefixes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<Fallback>,
+    /// Named overrides of the fields, applied when the profile is selected.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub profiles: IndexMap<String, serde_yaml::Value>,
 }
 
 impl MockConfig {
     /// Loads specs from the given file based on the file extension.
+    ///
+    /// The `${env:NAME}` and `${env:NAME:-default}` placeholders are replaced
+    /// by the environment variables (optionally typed by the `|int`, `|float`
+    /// or `|bool` suffix), and the overrides of the selected
+    /// `profile` are applied, when the file defines it.
     /// # Supported extensions:
     /// - `.yaml`, `.yml`
     /// - `.json`
-    pub fn load(file: impl AsRef<Path>) -> Result<Self> {
-        let config = match file.as_ref().extension().and_then(|s| s.to_str()) {
-            Some("yaml") | Some("yml") => Self::from_yaml(file)?,
-            Some("json") => Self::from_json(file)?,
-            _ => return Err(Error::Msg("Unsupported file type".into())),
+    pub fn load(
+        file: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Self> {
+        let file = file.as_ref();
+        let ext = file.extension().and_then(|s| s.to_str());
+        if !matches!(ext, Some("yaml" | "yml" | "json")) {
+            return Err(Error::Msg("Unsupported file type".into()));
+        }
+
+        let content = fs::read_to_string(file)?;
+        // YAML parser is used for JSON as well, since YAML is superset of
+        // JSON.
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&content)?;
+        let overrides = profile.and_then(|profile| {
+            value.get("profiles").and_then(|p| p.get(profile)).cloned()
+        });
+        let merged = overrides.is_some();
+        if let Some(overrides) = overrides {
+            merge_value(&mut value, overrides);
+        }
+        // The placeholders in the unselected profiles are not substituted,
+        // so that their variables don't have to be set.
+        let profiles = value
+            .as_mapping_mut()
+            .and_then(|map| map.remove("profiles"));
+        let substituted = substitute_env(&mut value)?;
+        if let (Some(map), Some(profiles)) = (value.as_mapping_mut(), profiles)
+        {
+            map.insert("profiles".into(), profiles);
+        }
+
+        // The file text is parsed again when it's unchanged, so that the
+        // errors point to the line in the file.
+        let mut config: Self = if merged || substituted {
+            serde_yaml::from_value(value)?
+        } else if ext == Some("json") {
+            serde_json::from_str(&content)?
+        } else {
+            serde_yaml::from_str(&content)?
         };
 
         for key in config.templates.keys() {
@@ -48,8 +128,34 @@ impl MockConfig {
                 )));
             }
         }
-        for spec in config.specs.iter() {
+        for fallback in config.fallbacks.iter_mut() {
+            fallback.normalize()?;
+        }
+        let responses = config
+            .on_invalid
+            .iter()
+            .chain(&config.not_found)
+            .chain(config.fallbacks.iter().map(|f| &f.response));
+        for res in responses {
+            res.validate()?;
+        }
+        if let Some(delay) =
+            config.mount.as_ref().and_then(|m| m.delay.as_ref())
+        {
+            delay.validate()?;
+        }
+        let dir = file.parent().unwrap_or(Path::new("."));
+        for spec in config.specs.iter_mut() {
             spec.validate()?;
+            if let Some(request) = &mut spec.request {
+                request.load(dir)?;
+            }
+            if let Some(graphql) = &mut spec.graphql {
+                graphql.load_schema(dir)?;
+            }
+            if let Some(grpc) = &mut spec.grpc {
+                grpc.load(dir, &spec.url)?;
+            }
         }
         Ok(config)
     }
@@ -66,24 +172,12 @@ impl MockConfig {
         }
     }
 
-    /// Loads specs from the given yaml file
-    pub fn from_yaml(file: impl AsRef<Path>) -> Result<Self> {
-        let buffer = BufReader::new(File::open(file)?);
-        serde_yaml::from_reader(buffer).map_err(Into::into)
-    }
-
     /// Saves the specs into given yaml file
     pub fn to_yaml(&self, file: impl AsRef<Path>) -> Result<()> {
         let buffer = BufWriter::new(File::create(file)?);
         serde_yaml::to_writer(buffer, self).map_err(Into::into)
     }
 
-    /// Loads specs from the given json file
-    pub fn from_json(file: impl AsRef<Path>) -> Result<Self> {
-        let buffer = BufReader::new(File::open(file)?);
-        serde_json::from_reader(buffer).map_err(Into::into)
-    }
-
     /// Saves the specs into given json file
     pub fn to_json(&self, file: impl AsRef<Path>) -> Result<()> {
         let buffer = BufWriter::new(File::create(file)?);
@@ -100,3 +194,118 @@ impl TryFrom<OpenApiV3Spec> for MockConfig {
         Import::oas3_to_specs(value)
     }
 }
+
+/// Replaces the `${env:NAME}` and `${env:NAME:-default}` placeholders in the
+/// string values with the values of the environment variables. The default
+/// is used when the variable is not set or is empty. Returns whether any
+/// placeholder was replaced.
+///
+/// The values stay strings, unless the placeholder is the whole string and
+/// selects the type by the `|int`, `|float` or `|bool` suffix (e.g.
+/// `${env:PORT:-80|int}`).
+fn substitute_env(value: &mut serde_yaml::Value) -> Result<bool> {
+    static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(concat!(
+            r"\$\{env:([A-Za-z_][A-Za-z0-9_]*)",
+            r"(:-([^}]*?))?(\|(int|float|bool))?\}",
+        ))
+        .unwrap()
+    });
+
+    let s = match value {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Sequence(seq) => {
+            let mut substituted = false;
+            for item in seq {
+                substituted |= substitute_env(item)?;
+            }
+            return Ok(substituted);
+        }
+        serde_yaml::Value::Mapping(map) => {
+            let mut substituted = false;
+            for item in map.values_mut() {
+                substituted |= substitute_env(item)?;
+            }
+            return Ok(substituted);
+        }
+        serde_yaml::Value::Tagged(tagged) => {
+            return substitute_env(&mut tagged.value);
+        }
+        _ => return Ok(false),
+    };
+    if !PLACEHOLDER.is_match(s) {
+        return Ok(false);
+    }
+
+    let mut missing = None;
+    let res = PLACEHOLDER.replace_all(s, |caps: &Captures| {
+        let default = caps.get(3).map(|d| d.as_str());
+        let value = std::env::var(&caps[1])
+            .ok()
+            .filter(|v| !v.is_empty() || default.is_none());
+        match (value, default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.to_owned(),
+            (None, None) => {
+                missing.get_or_insert_with(|| caps[1].to_owned());
+                String::new()
+            }
+        }
+    });
+    if let Some(name) = missing {
+        return Err(Error::Msg(format!(
+            "Environment variable '{name}' is not set and has no default"
+        )));
+    }
+
+    let typed = PLACEHOLDER.captures_iter(s).find_map(|caps| {
+        let ty = caps.get(5)?.as_str();
+        Some((caps.get(0)?.len() == s.len(), ty, caps[1].to_owned()))
+    });
+    *value = match typed {
+        None => serde_yaml::Value::String(res.into_owned()),
+        Some((false, ty, name)) => {
+            return Err(Error::Msg(format!(
+                "Placeholder of the environment variable '{name}' with type \
+                '{ty}' must be the whole value"
+            )));
+        }
+        Some((true, ty, name)) => typed_value(&res, ty).ok_or_else(|| {
+            Error::Msg(format!(
+                "Environment variable '{name}' value '{res}' is not a valid \
+                {ty}"
+            ))
+        })?,
+    };
+    Ok(true)
+}
+
+/// Parses the substituted value as the type selected by the placeholder.
+fn typed_value(value: &str, ty: &str) -> Option<serde_yaml::Value> {
+    let value = value.trim();
+    Some(match ty {
+        "int" => value.parse::<i64>().ok()?.into(),
+        "float" => value.parse::<f64>().ok()?.into(),
+        _ => value.parse::<bool>().ok()?.into(),
+    })
+}
+
+/// Merges the `overrides` into the `value`. Mappings are merged recursively,
+/// other values are replaced. Sequences are replaced as a whole, since their
+/// items have no key to match the overrides by.
+fn merge_value(value: &mut serde_yaml::Value, overrides: serde_yaml::Value) {
+    match (value, overrides) {
+        (
+            serde_yaml::Value::Mapping(map),
+            serde_yaml::Value::Mapping(overrides),
+        ) => {
+            for (key, over) in overrides {
+                match map.get_mut(&key) {
+                    Some(cur) => merge_value(cur, over),
+                    None => _ = map.insert(key, over),
+                }
+            }
+        }
+        (value, overrides) => *value = overrides,
+    }
+}
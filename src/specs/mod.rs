@@ -1,59 +1,178 @@
-use std::path::Path;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
 use log::{error, info};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::runtime::Handle;
 
 use crate::{
-    server::router::{Router, SharedRouter},
-    specs::mock_config::MockConfig,
+    error::Result,
+    server::{reload::ReloadStatus, router::SharedRouter},
+    specs::sources::{LoadedSpecs, Source},
 };
 
+pub mod auth;
 pub mod body;
+pub mod cors;
+pub mod delay;
+pub mod fallback;
+pub mod graphql;
+pub mod grpc;
 pub mod method;
 pub mod mock_config;
+pub mod mount;
+pub mod oidc;
+pub mod pattern;
+pub mod rate_limit;
+pub mod request;
 pub mod response;
+pub mod sources;
 pub mod spec;
 pub mod status_code;
+pub mod validation;
+pub mod websocket;
+
+/// Time without any change after which the specification is reloaded, so
+/// that files written in several steps are not reloaded half written.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
 
+/// Watches the directories of the specification files and reloads the
+/// router when any of the files, or files matching the sources, change.
+///
+/// Must be called from the tokio runtime, the router is replaced on it.
 pub fn watch_specs(
-    path: impl AsRef<Path>,
+    sources: Vec<Source>,
+    profile: Option<String>,
+    mut specs: LoadedSpecs,
     router: SharedRouter,
-) -> notify::Result<RecommendedWatcher> {
-    let file = path.as_ref().to_owned();
-    let mut watcher = notify::recommended_watcher(move |res| {
-        let router = router.clone();
-        let event: notify::Event = match res {
-            Ok(event) => event,
-            Err(e) => {
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watched = HashSet::new();
+    watch_dirs(&mut watcher, &mut watched, &specs)?;
+    let runtime = Handle::current();
+
+    // The thread owns the watcher, so that newly included directories can
+    // be watched after the reload.
+    std::thread::spawn(move || {
+        while let Ok(res) = rx.recv() {
+            if !is_relevant(res, &specs) {
+                continue;
+            }
+            // Waits until the changes settle. Changes of other files in the
+            // watched directories don't postpone the reload.
+            let mut deadline = Instant::now() + RELOAD_DEBOUNCE;
+            while let Ok(res) = rx.recv_timeout(
+                deadline.saturating_duration_since(Instant::now()),
+            ) {
+                if is_relevant(res, &specs) {
+                    deadline = Instant::now() + RELOAD_DEBOUNCE;
+                }
+            }
+
+            let profile = profile.as_deref();
+            let reload = reload_specs(&sources, profile, &router);
+            let Some(loaded) = runtime.block_on(reload) else {
+                continue;
+            };
+            specs = loaded;
+            if let Err(e) = watch_dirs(&mut watcher, &mut watched, &specs) {
                 error!("Watching specification: {e}.");
-                return;
             }
-        };
+        }
+    });
+    Ok(())
+}
 
-        if event.kind.is_create() || event.kind.is_modify() {
-            reload_specs(&file, router);
+/// Checks whether the event changes any of the specification files.
+fn is_relevant(
+    res: notify::Result<notify::Event>,
+    specs: &LoadedSpecs,
+) -> bool {
+    let event = match res {
+        Ok(event) => event,
+        Err(e) => {
+            error!("Watching specification: {e}.");
+            return false;
         }
-    })?;
+    };
 
-    watcher.watch(path.as_ref(), RecursiveMode::NonRecursive)?;
-    Ok(watcher)
+    let changed = event.kind.is_create()
+        || event.kind.is_modify()
+        || event.kind.is_remove();
+    changed
+        && event.paths.iter().any(|p| {
+            specs.files.contains(p)
+                || specs.sources.iter().any(|s| s.contains(p))
+        })
 }
 
-fn reload_specs(file: &Path, router: SharedRouter) {
-    let specs = match MockConfig::load(file) {
-        Ok(specs) => specs,
-        Err(e) => {
-            error!("Reloading specification: {e}.");
-            return;
+/// Starts watching the directories of the loaded files and sources that
+/// aren't watched yet.
+fn watch_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    specs: &LoadedSpecs,
+) -> notify::Result<()> {
+    let dirs = specs
+        .files
+        .iter()
+        .filter_map(|f| f.parent().map(Path::to_owned))
+        .chain(specs.sources.iter().map(Source::dir));
+    for dir in dirs {
+        if !watched.contains(&dir) {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            watched.insert(dir);
+        }
+    }
+    Ok(())
+}
+
+/// Reloads the specification and replaces the router. Unchanged endpoints
+/// keep their state. When the reload fails, the current router is kept and
+/// the error is stored as its reload status.
+async fn reload_specs(
+    sources: &[Source],
+    profile: Option<&str>,
+    router: &SharedRouter,
+) -> Option<LoadedSpecs> {
+    let reloaded = match LoadedSpecs::load(sources, profile) {
+        Ok(mut specs) => {
+            let config = std::mem::take(&mut specs.config);
+            router
+                .read()
+                .await
+                .reload(config)
+                .map(|(new_router, diff)| (specs, new_router, diff))
         }
+        Err(e) => Err(e),
     };
 
-    match Router::new(specs) {
-        Ok(new_router) => {
-            let mut guard = router.blocking_write();
-            *guard = new_router;
-            info!("Specification reloaded.");
+    match reloaded {
+        Ok((specs, mut new_router, diff)) => {
+            info!("Specification reloaded: {diff}.");
+            for (kind, routes) in [
+                ("Added", &diff.added),
+                ("Removed", &diff.removed),
+                ("Changed", &diff.changed),
+            ] {
+                if !routes.is_empty() {
+                    info!("{kind} routes: {}.", routes.join(", "));
+                }
+            }
+            new_router.reload = Some(ReloadStatus::success(diff));
+            *router.write().await = new_router;
+            Some(specs)
+        }
+        Err(e) => {
+            error!("Reloading specification: {e}.");
+            router.write().await.reload =
+                Some(ReloadStatus::failure(e.to_string()));
+            None
         }
-        Err(e) => error!("Regenerating router: {e}"),
     }
 }
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    specs::{
+        auth::Auth, body::body::Body, cors::Cors, delay::Delay,
+        response::EndpointResponse,
+    },
+};
+
+/// URL prefix and default settings of the endpoints of a specification file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mount {
+    /// URL prefix of all the endpoints.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prefix: String,
+    /// Headers added to the responses which don't set them.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub headers: IndexMap<String, Body>,
+    /// Delay of the responses without their own delay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<Delay>,
+    /// Authentication of the endpoints without their own authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// CORS policy of all the requests under the prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors: Option<Cors>,
+}
+
+/// Specification file loaded by another specification file, optionally
+/// mounted with its own settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Include {
+    Path(PathBuf),
+    Mounted {
+        path: PathBuf,
+        #[serde(default)]
+        mount: Box<Mount>,
+    },
+}
+
+impl Mount {
+    /// Checks whether the prefix is valid.
+    pub fn validate(&self) -> Result<()> {
+        if !self.prefix.is_empty() && !self.prefix.starts_with('/') {
+            return Err(Error::Msg(format!(
+                "The mount prefix '{}' must start with '/'",
+                self.prefix
+            )));
+        }
+        Ok(())
+    }
+
+    /// Nests the mount under the `outer` mount. The prefixes are joined and
+    /// the settings of this mount take precedence.
+    pub fn nest(self, outer: &Mount) -> Mount {
+        let mut headers = outer.headers.clone();
+        headers.extend(self.headers);
+        Mount {
+            prefix: outer.url(&self.prefix),
+            headers,
+            delay: self.delay.or_else(|| outer.delay.clone()),
+            auth: self.auth.or_else(|| outer.auth.clone()),
+            cors: self.cors.or_else(|| outer.cors.clone()),
+        }
+    }
+
+    /// Prepends the prefix to the given URL.
+    pub fn url(&self, url: &str) -> String {
+        let prefix = self.prefix.trim_end_matches('/');
+        match url {
+            "" | "/" if !prefix.is_empty() => prefix.to_owned(),
+            _ => format!("{prefix}{url}"),
+        }
+    }
+
+    /// Sets the default headers and delay of the endpoint responses.
+    pub fn apply(&self, response: &mut EndpointResponse) {
+        for res in response.responses_mut() {
+            for (name, value) in &self.headers {
+                if !res.headers.keys().any(|h| h.eq_ignore_ascii_case(name)) {
+                    res.headers.insert(name.clone(), value.clone());
+                }
+            }
+            if res.delay.is_none() {
+                res.delay = self.delay.clone();
+            }
+        }
+    }
+}
+
+impl Include {
+    /// Gets the included path and its mount.
+    pub fn into_parts(self) -> (PathBuf, Option<Mount>) {
+        match self {
+            Include::Path(path) => (path, None),
+            Include::Mounted { path, mount } => (path, Some(*mount)),
+        }
+    }
+}
+
+/// Checks whether the URL path is under the prefix, matched by whole path
+/// segments.
+pub fn has_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+/// Configuration of the built-in OpenID Connect provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcConfig {
+    /// URL prefix of the provider endpoints.
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Token issuer, defaults to the server URL with the prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Token lifetime in seconds.
+    #[serde(default = "default_expires_in")]
+    pub expires_in: u64,
+    /// Refresh token lifetime in seconds.
+    #[serde(default = "default_refresh_expires_in")]
+    pub refresh_expires_in: u64,
+    /// Claims added to all the issued tokens.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub claims: Map<String, Value>,
+    /// Users that can log in. The first user is used when no user is
+    /// specified on authorization.
+    #[serde(default)]
+    pub users: Vec<OidcUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcUser {
+    pub username: String,
+    /// User password, any password is accepted when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// User claims, `sub` defaults to the username.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub claims: Map<String, Value>,
+}
+
+impl OidcConfig {
+    /// Checks that the prefix starts with `/` and removes its trailing `/`.
+    pub fn normalize(mut self) -> Result<Self> {
+        if !self.prefix.starts_with('/') {
+            return Err(Error::Msg(format!(
+                "The OIDC prefix '{}' must start with '/'",
+                self.prefix
+            )));
+        }
+        self.prefix = self.prefix.trim_end_matches('/').to_owned();
+        Ok(self)
+    }
+
+    /// Finds user with the given username, or the first user when no username
+    /// is given.
+    pub fn user(&self, username: Option<&str>) -> Option<&OidcUser> {
+        match username {
+            Some(name) => self.users.iter().find(|u| u.username == name),
+            None => self.users.first(),
+        }
+    }
+}
+
+impl OidcUser {
+    /// Gets the user claims, with `sub` defaulting to the username.
+    pub fn claims(&self) -> Map<String, Value> {
+        let mut claims = self.claims.clone();
+        claims
+            .entry("sub")
+            .or_insert_with(|| Value::String(self.username.clone()));
+        claims
+    }
+}
+
+fn default_prefix() -> String {
+    "/oidc".to_owned()
+}
+
+fn default_expires_in() -> u64 {
+    3600
+}
+
+fn default_refresh_expires_in() -> u64 {
+    86400
+}
@@ -0,0 +1,41 @@
+use std::hash::{Hash, Hasher};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Regular expression matched against the incoming values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(pub Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl Hash for Pattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_str().hash(state);
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Regex::new(&value)
+            .map(Pattern)
+            .map_err(|e| Error::Msg(format!("Invalid pattern: {e}")))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(value: Pattern) -> Self {
+        value.0.as_str().to_owned()
+    }
+}
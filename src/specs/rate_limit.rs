@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    HeaderMap,
+    header::{HeaderName, HeaderValue, RETRY_AFTER},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, server::url::var::UrlVar};
+
+/// Limits the number of requests in a time window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Maximum number of requests in a window.
+    pub requests: u64,
+    /// Window length in seconds.
+    #[serde(default = "default_window")]
+    pub window: u64,
+    /// What the requests are counted by.
+    #[serde(default)]
+    pub key: RateLimitKey,
+
+    #[serde(skip)]
+    pub windows: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+/// Specifies by what the rate limited requests are grouped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RateLimitKey {
+    /// All requests share the same counter.
+    Global,
+    /// Requests are counted per client IP address.
+    #[default]
+    Ip,
+    /// Requests are counted per value of the given header.
+    Header(String),
+    /// Requests are counted per value of the given URL variable.
+    Var(String),
+}
+
+/// Request counter of a single rate limit window.
+#[derive(Debug, Clone, Copy)]
+pub struct Window {
+    pub start: Instant,
+    pub count: u64,
+}
+
+/// State of the rate limit after counting a request.
+#[derive(Debug, Clone, Copy)]
+pub struct RateState {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset: Duration,
+    pub exceeded: bool,
+}
+
+impl RateLimit {
+    /// Counts the request with the given key and returns the limit state.
+    pub fn check(&self, key: String) -> RateState {
+        let window = Duration::from_secs(self.window);
+        let now = Instant::now();
+
+        let mut windows = self.windows.lock().unwrap();
+        windows.retain(|_, w| now.duration_since(w.start) < window);
+        let cur = windows.entry(key).or_insert(Window {
+            start: now,
+            count: 0,
+        });
+        cur.count += 1;
+
+        RateState {
+            limit: self.requests,
+            remaining: self.requests.saturating_sub(cur.count),
+            reset: window.saturating_sub(now.duration_since(cur.start)),
+            exceeded: cur.count > self.requests,
+        }
+    }
+
+    /// Takes over the request counters of the `previous` rate limit, so that
+    /// they survive the specification reload. The counters are reset only
+    /// when the requests are counted by a different key.
+    pub fn keep_windows(&mut self, previous: &RateLimit) {
+        if self.key == previous.key {
+            self.windows = previous.windows.clone();
+        }
+    }
+}
+
+impl RateLimitKey {
+    /// Gets the key value of the given request.
+    pub fn resolve(
+        &self,
+        headers: &HeaderMap,
+        ip: IpAddr,
+        vars: &HashMap<String, UrlVar>,
+    ) -> String {
+        match self {
+            RateLimitKey::Global => String::new(),
+            RateLimitKey::Ip => ip.to_string(),
+            RateLimitKey::Header(name) => headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_owned(),
+            RateLimitKey::Var(name) => {
+                vars.get(name).map(|v| v.to_string()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl RateState {
+    /// Inserts the `X-RateLimit-*` headers, and `Retry-After` when the limit
+    /// is exceeded.
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        let reset = self.reset.as_secs_f64().ceil() as u64;
+        let mut insert = |name: &'static str, value: u64| {
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from(value),
+            );
+        };
+        insert("x-ratelimit-limit", self.limit);
+        insert("x-ratelimit-remaining", self.remaining);
+        insert("x-ratelimit-reset", reset);
+        if self.exceeded {
+            headers.insert(RETRY_AFTER, HeaderValue::from(reset));
+        }
+    }
+}
+
+impl TryFrom<String> for RateLimitKey {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.split_once(':') {
+            None if value == "global" => Ok(Self::Global),
+            None if value == "ip" => Ok(Self::Ip),
+            Some(("header", name)) => Ok(Self::Header(name.to_lowercase())),
+            Some(("var", name)) => Ok(Self::Var(name.to_owned())),
+            _ => Err(Error::Msg(format!(
+                "Invalid rate limit key '{value}'. Expected `global`, `ip`, \
+                `header:<name>` or `var:<name>`."
+            ))),
+        }
+    }
+}
+
+impl From<RateLimitKey> for String {
+    fn from(value: RateLimitKey) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for RateLimitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitKey::Global => write!(f, "global"),
+            RateLimitKey::Ip => write!(f, "ip"),
+            RateLimitKey::Header(name) => write!(f, "header:{name}"),
+            RateLimitKey::Var(name) => write!(f, "var:{name}"),
+        }
+    }
+}
+
+fn default_window() -> u64 {
+    60
+}
@@ -0,0 +1,405 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use hyper::HeaderMap;
+use indexmap::IndexMap;
+use jsonschema::{
+    JsonType, Validator, error::TypeKind, error::ValidationErrorKind,
+    paths::LocationSegment,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    error::{Error, Result},
+    server::{
+        incoming::{
+            BodyKind, IncomingBody, cookie_fields, header_fields, query_fields,
+        },
+        url::var::UrlVar,
+    },
+    specs::{
+        body::body::Body,
+        request::{text::TextBody, xml::XmlBody},
+        status_code::StatusCode,
+        validation::{ValidationError, index_path, key_path},
+    },
+};
+
+pub mod text;
+pub mod xml;
+
+/// Keys of the structured request specification.
+const REQUEST_KEYS: &[&str] = &[
+    "body",
+    "json_schema",
+    "xml",
+    "text",
+    "query",
+    "headers",
+    "cookies",
+    "status",
+];
+
+/// Expected request - its body, query parameters, headers and cookies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "RawRequest", into = "RawRequest")]
+pub struct Request {
+    pub body: Option<RequestBody>,
+    pub query: IndexMap<String, Body>,
+    /// Expected headers, their names are case insensitive.
+    pub headers: IndexMap<String, Body>,
+    pub cookies: IndexMap<String, Body>,
+    /// Status of the response to the invalid request, defaults to 400.
+    pub status: Option<StatusCode>,
+}
+
+/// Request as written in the specification. The kind of the expected body
+/// is given by its key, so at most one of `body`, `json_schema`, `xml` and
+/// `text` can be set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<Body>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json_schema: Option<JsonSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    xml: Option<XmlBody>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<TextBody>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    query: IndexMap<String, Body>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    headers: IndexMap<String, Body>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    cookies: IndexMap<String, Body>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<StatusCode>,
+}
+
+/// Expected request body, either body template, JSON Schema, XML paths or
+/// plain text rules.
+#[derive(Debug, Clone)]
+pub enum RequestBody {
+    Schema(JsonSchema),
+    Xml(XmlBody),
+    Text(TextBody),
+    Body(Body),
+}
+
+/// JSON Schema (draft 2020-12) the request body has to conform to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonSchema {
+    /// Path to the schema document or the inline schema.
+    pub json_schema: serde_json::Value,
+
+    #[serde(skip)]
+    pub validator: Option<Arc<Validator>>,
+}
+
+impl Request {
+    /// Loads the JSON Schema of the body, relative paths are resolved
+    /// against `dir`. Header names are normalized to lowercase.
+    pub fn load(&mut self, dir: &Path) -> Result<()> {
+        self.headers = std::mem::take(&mut self.headers)
+            .into_iter()
+            .map(|(name, exp)| (name.to_ascii_lowercase(), exp))
+            .collect();
+        match &mut self.body {
+            Some(body) => body.load(dir),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the query parameters, headers and cookies of the incoming
+    /// request and returns all the found errors.
+    pub fn validate_params(
+        &self,
+        query: Option<&str>,
+        headers: &HeaderMap,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        let mut check = |exp: &IndexMap<String, Body>, inc, root| {
+            check_params(exp, &inc, root, vars, templates, &mut errors)
+        };
+        if !self.query.is_empty() {
+            check(&self.query, query_fields(query), "query");
+        }
+        if !self.headers.is_empty() {
+            check(&self.headers, header_fields(headers), "headers");
+        }
+        if !self.cookies.is_empty() {
+            check(&self.cookies, cookie_fields(headers), "cookies");
+        }
+        errors
+    }
+}
+
+impl TryFrom<RawRequest> for Request {
+    type Error = Error;
+
+    fn try_from(raw: RawRequest) -> Result<Self> {
+        let mut bodies = [
+            raw.body.map(RequestBody::Body),
+            raw.json_schema.map(RequestBody::Schema),
+            raw.xml.map(RequestBody::Xml),
+            raw.text.map(RequestBody::Text),
+        ]
+        .into_iter()
+        .flatten();
+        let body = bodies.next();
+        if bodies.next().is_some() {
+            return Err(Error::Msg(
+                "Request can have only one of `body`, `json_schema`, `xml` \
+                and `text`"
+                    .into(),
+            ));
+        }
+        Ok(Self {
+            body,
+            query: raw.query,
+            headers: raw.headers,
+            cookies: raw.cookies,
+            status: raw.status,
+        })
+    }
+}
+
+impl From<Request> for RawRequest {
+    fn from(value: Request) -> Self {
+        let mut raw = RawRequest {
+            query: value.query,
+            headers: value.headers,
+            cookies: value.cookies,
+            status: value.status,
+            ..Default::default()
+        };
+        match value.body {
+            Some(RequestBody::Body(body)) => raw.body = Some(body),
+            Some(RequestBody::Schema(schema)) => {
+                raw.json_schema = Some(schema)
+            }
+            Some(RequestBody::Xml(xml)) => raw.xml = Some(xml),
+            Some(RequestBody::Text(text)) => raw.text = Some(text),
+            None => {}
+        }
+        raw
+    }
+}
+
+impl From<RequestBody> for Request {
+    fn from(value: RequestBody) -> Self {
+        Self {
+            body: Some(value),
+            ..Default::default()
+        }
+    }
+}
+
+/// Deserializes either the structured [`Request`] or only the expected body,
+/// which is a shorthand for the request with the `body`.
+///
+/// Mapping is the structured request when it's not empty and all its keys
+/// are the structured request keys. Body with such fields must be written
+/// under the `body` key.
+pub fn deserialize_request<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Request>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(value) = Option::<serde_yaml::Value>::deserialize(deserializer)?
+    else {
+        return Ok(None);
+    };
+
+    let request = if is_structured(&value) {
+        serde_yaml::from_value(value).map_err(|e| {
+            Error::Msg(format!(
+                "Invalid request: {e}. Expected body with fields named as \
+                the request keys must be written under the 'body' key"
+            ))
+        })
+    } else {
+        serde_yaml::from_value(value)
+            .map(|b| RequestBody::Body(b).into())
+            .map_err(Error::from)
+    };
+    request.map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Serializes the request with only the expected body as the shorthand,
+/// unless the body would be read as the structured request.
+pub fn serialize_request<S>(
+    request: &Option<Request>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if let Some(Request {
+        body: Some(RequestBody::Body(body)),
+        query,
+        headers,
+        cookies,
+        status: None,
+    }) = request
+        && query.is_empty()
+        && headers.is_empty()
+        && cookies.is_empty()
+        && serde_yaml::to_value(body).is_ok_and(|v| !is_structured(&v))
+    {
+        return body.serialize(serializer);
+    }
+    request.serialize(serializer)
+}
+
+/// Checks whether the value is written as the structured request.
+fn is_structured(value: &serde_yaml::Value) -> bool {
+    value.as_mapping().is_some_and(|map| {
+        !map.is_empty()
+            && map
+                .keys()
+                .all(|k| k.as_str().is_some_and(|k| REQUEST_KEYS.contains(&k)))
+    })
+}
+
+/// Checks the incoming parameters against the expected ones.
+fn check_params(
+    exp: &IndexMap<String, Body>,
+    inc: &serde_yaml::Mapping,
+    root: &str,
+    vars: &HashMap<String, UrlVar>,
+    templates: &HashMap<String, Body>,
+    errors: &mut Vec<ValidationError>,
+) {
+    for (name, exp) in exp {
+        let key = serde_yaml::Value::String(name.clone());
+        let path = key_path(root, &key);
+        match inc.get(&key) {
+            Some(value) => exp.check(value, vars, templates, &path, errors),
+            None if !exp.is_optional() => errors.push(
+                ValidationError::new(&path, "missing field")
+                    .expected(exp.type_name(), "missing"),
+            ),
+            None => {}
+        }
+    }
+}
+
+impl RequestBody {
+    /// Loads the JSON Schema, relative paths are resolved against `dir`.
+    pub fn load(&mut self, dir: &Path) -> Result<()> {
+        match self {
+            RequestBody::Schema(schema) => schema.load(dir),
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the incoming body and returns all the found errors.
+    /// Fields of the form bodies and values captured by the XML and text
+    /// rules are added to the `vars`. Form fields don't replace the URL
+    /// variables of the same name.
+    pub fn validate(
+        &self,
+        inc: &IncomingBody,
+        vars: &mut HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Vec<ValidationError> {
+        if matches!(inc.kind, BodyKind::Form | BodyKind::Multipart)
+            && let serde_yaml::Value::Mapping(fields) = &inc.value
+        {
+            for (key, value) in fields {
+                if let Some(key) = key.as_str() {
+                    vars.entry(key.to_owned())
+                        .or_insert_with(|| UrlVar::Value(value.clone()));
+                }
+            }
+        }
+
+        match self {
+            RequestBody::Schema(schema) => schema.validate(&inc.value),
+            RequestBody::Body(body) => {
+                let mut errors = vec![];
+                body.check(&inc.value, vars, templates, "$", &mut errors);
+                errors
+            }
+            RequestBody::Xml(_) if inc.kind != BodyKind::Xml => {
+                vec![
+                    ValidationError::new("$", "expected XML body")
+                        .expected("application/xml", &inc.content_type),
+                ]
+            }
+            RequestBody::Xml(xml) => {
+                xml.validate(&inc.text(), vars, templates)
+            }
+            RequestBody::Text(text) => text.validate(&inc.text(), vars),
+        }
+    }
+}
+
+impl JsonSchema {
+    fn load(&mut self, dir: &Path) -> Result<()> {
+        let schema = match &self.json_schema {
+            serde_json::Value::String(path) => {
+                let path = dir.join(path);
+                let file = std::fs::File::open(&path).map_err(|e| {
+                    Error::Msg(format!(
+                        "Reading JSON Schema '{}': {e}",
+                        path.display()
+                    ))
+                })?;
+                // YAML is superset of JSON, so this loads both formats
+                serde_yaml::from_reader(file)?
+            }
+            schema => schema.clone(),
+        };
+        let validator = jsonschema::draft202012::new(&schema)
+            .map_err(|e| Error::Msg(format!("Invalid JSON Schema: {e}")))?;
+        self.validator = Some(Arc::new(validator));
+        Ok(())
+    }
+
+    fn validate(&self, inc: &serde_yaml::Value) -> Vec<ValidationError> {
+        let Some(validator) = &self.validator else {
+            return vec![];
+        };
+        let Ok(inc) = serde_json::to_value(inc) else {
+            return vec![ValidationError::new("$", "body is not valid JSON")];
+        };
+
+        validator
+            .iter_errors(&inc)
+            .map(|e| {
+                let mut path = "$".to_owned();
+                for segment in e.instance_path().iter() {
+                    path = match segment {
+                        LocationSegment::Property(key) => {
+                            key_path(&path, &key.as_ref().into())
+                        }
+                        LocationSegment::Index(i) => index_path(&path, i),
+                    };
+                }
+
+                let err = ValidationError::new(&path, e.to_string());
+                match e.kind() {
+                    ValidationErrorKind::Type { kind } => {
+                        let expected = match kind {
+                            TypeKind::Single(ty) => ty.to_string(),
+                            TypeKind::Multiple(types) => types
+                                .iter()
+                                .map(|t| t.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" or "),
+                        };
+                        let actual = JsonType::from(e.instance().as_ref());
+                        err.expected(expected, actual.to_string())
+                    }
+                    _ => err,
+                }
+            })
+            .collect()
+    }
+}
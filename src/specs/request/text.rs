@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    server::url::var::UrlVar,
+    specs::{pattern::Pattern, validation::ValidationError},
+};
+
+/// Expected plain text request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TextBody {
+    pub text: TextMatch,
+}
+
+/// Rules the plain text body has to match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextMatch {
+    /// Regular expression the body has to match. It isn't anchored, so `^` and
+    /// `$` have to be used to match the whole body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<Pattern>,
+    /// Text the body has to contain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+}
+
+impl TextBody {
+    /// Validates the incoming text. Named groups of the matched pattern are
+    /// added to the `vars`.
+    pub fn validate(
+        &self,
+        text: &str,
+        vars: &mut HashMap<String, UrlVar>,
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        if let Some(contains) = &self.text.contains
+            && !text.contains(contains.as_str())
+        {
+            errors.push(
+                ValidationError::new("$", "body does not contain text")
+                    .expected(format!("containing {contains:?}"), text),
+            );
+        }
+
+        if let Some(pattern) = &self.text.pattern {
+            let Some(captures) = pattern.0.captures(text) else {
+                errors.push(
+                    ValidationError::new("$", "body does not match pattern")
+                        .expected(pattern.0.as_str(), text),
+                );
+                return errors;
+            };
+            for name in pattern.0.capture_names().flatten() {
+                if let Some(value) = captures.name(name) {
+                    vars.entry(name.to_owned()).or_insert_with(|| {
+                        UrlVar::String(value.as_str().to_owned())
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    server::{incoming::scalar, url::var::UrlVar},
+    specs::{body::body::Body, validation::ValidationError},
+};
+
+/// Expected XML request body. Values of the document are selected by
+/// XPath-like paths and checked against the expected body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct XmlBody {
+    pub xml: IndexMap<XmlPath, Body>,
+}
+
+/// Path to the XML element or attribute, such as `/order/item[2]/@id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct XmlPath {
+    path: String,
+    steps: Vec<XmlStep>,
+    attr: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct XmlStep {
+    name: String,
+    /// One-based index of the element among its siblings of the same name.
+    index: usize,
+}
+
+impl XmlBody {
+    /// Validates the incoming XML document. Selected values are added to
+    /// the `vars` under the name of the last path segment.
+    pub fn validate(
+        &self,
+        doc: &str,
+        vars: &mut HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Vec<ValidationError> {
+        let doc = match roxmltree::Document::parse(doc) {
+            Ok(doc) => doc,
+            Err(e) => {
+                return vec![ValidationError::new(
+                    "$",
+                    format!("invalid XML: {e}"),
+                )];
+            }
+        };
+
+        let mut errors = vec![];
+        for (path, exp) in self.xml.iter() {
+            let Some(value) = path.select(&doc) else {
+                if !exp.is_optional() {
+                    errors.push(
+                        ValidationError::new(&path.path, "missing field")
+                            .expected(exp.type_name(), "missing"),
+                    );
+                }
+                continue;
+            };
+
+            let value = scalar(&value);
+            exp.check(&value, vars, templates, &path.path, &mut errors);
+            vars.entry(path.name().to_owned())
+                .or_insert(UrlVar::Value(value));
+        }
+        errors
+    }
+}
+
+impl XmlPath {
+    /// Gets text of the selected element or value of the attribute.
+    fn select(&self, doc: &roxmltree::Document) -> Option<String> {
+        let mut node = doc.root();
+        for step in &self.steps {
+            node = node
+                .children()
+                .filter(|n| n.is_element() && step.matches(n))
+                .nth(step.index - 1)?;
+        }
+
+        match &self.attr {
+            Some(attr) => node.attribute(attr.as_str()).map(str::to_owned),
+            None => {
+                let text: String = node
+                    .descendants()
+                    .filter_map(|n| n.is_text().then(|| n.text()).flatten())
+                    .collect();
+                Some(text.trim().to_owned())
+            }
+        }
+    }
+
+    /// Gets the name of the selected element or attribute.
+    fn name(&self) -> &str {
+        self.attr
+            .as_deref()
+            .or(self.steps.last().map(|s| s.name.as_str()))
+            .unwrap_or_default()
+    }
+}
+
+impl XmlStep {
+    fn matches(&self, node: &roxmltree::Node) -> bool {
+        self.name == "*" || node.tag_name().name() == self.name
+    }
+}
+
+impl TryFrom<String> for XmlPath {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        let invalid = || Error::Msg(format!("Invalid XML path '{value}'"));
+        let Some(rest) = value.strip_prefix('/') else {
+            return Err(invalid());
+        };
+
+        let mut steps = vec![];
+        let mut attr = None;
+        let mut segments = rest.split('/').peekable();
+        while let Some(segment) = segments.next() {
+            if let Some(name) = segment.strip_prefix('@') {
+                if segments.peek().is_some() || name.is_empty() {
+                    return Err(invalid());
+                }
+                attr = Some(name.to_owned());
+                break;
+            }
+
+            let (name, index) = match segment.split_once('[') {
+                Some((name, index)) => {
+                    let index = index
+                        .strip_suffix(']')
+                        .and_then(|i| i.parse().ok())
+                        .filter(|i| *i > 0)
+                        .ok_or_else(invalid)?;
+                    (name, index)
+                }
+                None => (segment, 1),
+            };
+            if name.is_empty() {
+                return Err(invalid());
+            }
+            steps.push(XmlStep {
+                name: name.to_owned(),
+                index,
+            });
+        }
+
+        if steps.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            path: value,
+            steps,
+            attr,
+        })
+    }
+}
+
+impl From<XmlPath> for String {
+    fn from(value: XmlPath) -> Self {
+        value.path
+    }
+}
+
+impl PartialEq for XmlPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for XmlPath {}
+
+impl Hash for XmlPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use http_body_util::{BodyExt, channel::Channel};
+use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{
+    error::{Error, Result},
+    server::{ResBody, url::var::UrlVar},
+    specs::{body::body::Body, delay::Delay},
+};
+
+/// Server-Sent Events streamed as the response body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Events {
+    /// How many times the list of events is sent.
+    #[serde(default)]
+    pub repeat: Repeat,
+    pub items: Vec<Event>,
+}
+
+/// Single Server-Sent Event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<Body>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Body>,
+    #[serde(default, skip_serializing_if = "Body::is_null")]
+    pub data: Body,
+    /// Delay before sending the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<Delay>,
+}
+
+/// Number of repetitions, either count or `forever`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RepeatValue", into = "RepeatValue")]
+pub enum Repeat {
+    Times(u32),
+    Forever,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RepeatValue {
+    Times(u32),
+    Keyword(String),
+}
+
+impl Events {
+    /// Checks that there's at least one event and the event delays are
+    /// valid.
+    pub fn validate(&self) -> Result<()> {
+        if self.items.is_empty() {
+            return Err(Error::Msg(
+                "The events must contain at least one item".into(),
+            ));
+        }
+        for delay in self.items.iter().filter_map(|e| e.delay.as_ref()) {
+            delay.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Creates body streaming the events. The events are rendered right
+    /// before being sent, so each repetition gets new fake values.
+    pub fn stream(
+        &self,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> ResBody {
+        let (events, vars, templates) =
+            (self.clone(), vars.clone(), templates.clone());
+        let (mut tx, rx) = Channel::new(1);
+        tokio::spawn(async move {
+            let mut round = 0;
+            while events.repeat.allows(round) {
+                for event in &events.items {
+                    if let Some(delay) = &event.delay {
+                        sleep(delay.sample()).await;
+                    }
+                    let frame = event.render(&vars, &templates);
+                    if tx.send_data(Bytes::from(frame)).await.is_err() {
+                        return;
+                    }
+                }
+                round = round.saturating_add(1);
+            }
+        });
+        rx.boxed()
+    }
+}
+
+impl Event {
+    /// Renders the event in the `text/event-stream` format.
+    fn render(
+        &self,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> String {
+        let mut frame = String::new();
+        let fields = [("event", &self.event), ("id", &self.id)];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                let value = render_value(value, vars, templates);
+                let value = value.replace(['\r', '\n'], " ");
+                frame += &format!("{name}: {value}\n");
+            }
+        }
+        let data = render_value(&self.data, vars, templates);
+        for line in data.lines() {
+            frame += &format!("data: {line}\n");
+        }
+        frame + "\n"
+    }
+}
+
+impl Repeat {
+    /// Checks whether the round with the given zero-based index is sent.
+    fn allows(&self, round: u32) -> bool {
+        match self {
+            Repeat::Times(times) => round < *times,
+            Repeat::Forever => true,
+        }
+    }
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Repeat::Times(1)
+    }
+}
+
+impl TryFrom<RepeatValue> for Repeat {
+    type Error = Error;
+
+    fn try_from(value: RepeatValue) -> Result<Self> {
+        match value {
+            RepeatValue::Times(times) => Ok(Repeat::Times(times)),
+            RepeatValue::Keyword(k) if k == "forever" => Ok(Repeat::Forever),
+            RepeatValue::Keyword(k) => Err(Error::Msg(format!(
+                "Invalid repeat '{k}', expected number or 'forever'"
+            ))),
+        }
+    }
+}
+
+impl From<Repeat> for RepeatValue {
+    fn from(value: Repeat) -> Self {
+        match value {
+            Repeat::Times(times) => RepeatValue::Times(times),
+            Repeat::Forever => RepeatValue::Keyword("forever".into()),
+        }
+    }
+}
+
+/// Renders the value, strings are used as is, other values as JSON.
+fn render_value(
+    body: &Body,
+    vars: &HashMap<String, UrlVar>,
+    templates: &HashMap<String, Body>,
+) -> String {
+    match body.resolve(vars, templates) {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Null => String::new(),
+        value => serde_json::to_string(&value).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,131 @@
+use std::{collections::HashMap, time::Duration};
+
+use fake::rand;
+use http_body_util::{BodyExt, channel::Channel};
+use hyper::{body::Bytes, header::CONTENT_LENGTH};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    server::{HyperRes, full_body, url::var::UrlVar},
+    specs::{body::body::Body, response::Response, status_code::StatusCode},
+};
+
+/// Interval between the chunks of the trickled response body.
+const TRICKLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Simulates failure of the endpoint response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fault {
+    #[serde(flatten)]
+    pub mode: FaultMode,
+    /// Probability of the fault being injected, in range from 0 to 1.
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FaultMode {
+    /// Closes the connection without responding.
+    Close,
+    /// Resets the connection in the middle of sending the body.
+    Reset,
+    /// Sends only the first half of the body.
+    Truncate,
+    /// Sends invalid JSON body.
+    Malformed,
+    /// Sends the body at the given rate (bytes per second).
+    Trickle { rate: u64 },
+    /// Sends empty response with the given status.
+    Empty {
+        #[serde(default)]
+        status: StatusCode,
+    },
+}
+
+impl Fault {
+    /// Checks that the probability is in range from 0 to 1.
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(Error::Msg(format!(
+                "The fault probability {} must be between 0 and 1",
+                self.probability
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks whether the fault should be injected, based on its probability.
+    pub fn triggers(&self) -> bool {
+        self.probability >= 1.0 || rand::random::<f64>() < self.probability
+    }
+
+    /// Creates HTTP response of the given response with the fault injected.
+    ///
+    /// Returns error when the connection should be closed without response.
+    pub fn apply(
+        &self,
+        res: &Response,
+        body: String,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Result<HyperRes> {
+        info!("Injecting {:?} fault into the response.", self.mode);
+        let builder = res.http_builder(vars, templates);
+        let res = match &self.mode {
+            FaultMode::Close => {
+                return Err(Error::Msg(
+                    "fault injection closed the connection".into(),
+                ));
+            }
+            FaultMode::Reset => {
+                let len = body.len();
+                let (mut tx, rx) = Channel::new(1);
+                tokio::spawn(async move {
+                    let half = Bytes::from(body).slice(..len / 2);
+                    _ = tx.send_data(half).await;
+                    tx.abort(std::io::ErrorKind::ConnectionReset.into());
+                });
+                builder.header(CONTENT_LENGTH, len).body(rx.boxed())
+            }
+            FaultMode::Truncate => {
+                let mut body = body;
+                body.truncate(body.floor_char_boundary(body.len() / 2));
+                builder.body(full_body(body))
+            }
+            FaultMode::Malformed => {
+                let mut body = body;
+                body.pop();
+                body.push(',');
+                builder.body(full_body(body))
+            }
+            FaultMode::Trickle { rate } => {
+                let chunk = (*rate as usize / 10).max(1);
+                let (mut tx, rx) = Channel::new(1);
+                let len = body.len();
+                tokio::spawn(async move {
+                    let body = Bytes::from(body);
+                    for start in (0..body.len()).step_by(chunk) {
+                        let end = (start + chunk).min(body.len());
+                        if tx.send_data(body.slice(start..end)).await.is_err()
+                        {
+                            return;
+                        }
+                        tokio::time::sleep(TRICKLE_INTERVAL).await;
+                    }
+                });
+                builder.header(CONTENT_LENGTH, len).body(rx.boxed())
+            }
+            FaultMode::Empty { status } => hyper::Response::builder()
+                .status(status.0)
+                .body(full_body(Bytes::new())),
+        };
+        res.map_err(Into::into)
+    }
+}
+
+fn default_probability() -> f64 {
+    1.0
+}
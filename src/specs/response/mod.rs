@@ -1,7 +1,12 @@
+mod events;
+mod fault;
 mod multi_response;
+#[allow(clippy::module_inception)]
 mod response;
 mod strategy;
 
+pub use events::{Event, Events, Repeat};
+pub use fault::{Fault, FaultMode};
 pub use multi_response::MultiResponse;
 pub use response::Response;
 pub use strategy::Strategy;
@@ -12,23 +17,61 @@ use serde::{Deserialize, Serialize};
 #[serde(untagged)]
 pub enum EndpointResponse {
     Multi(MultiResponse),
-    Single(Response),
+    Single(Box<Response>),
 }
 
 impl EndpointResponse {
     /// Gets the endpoint response.
     ///
-    /// When multiple response, picks based on the set strategy.
-    pub fn get(&self) -> &Response {
+    /// When multiple response, picks the response selected by the `hint`
+    /// (name or index) or based on the set strategy.
+    pub fn get(&self, hint: Option<&str>) -> &Response {
         match self {
             EndpointResponse::Single(res) => res,
-            EndpointResponse::Multi(multi) => multi.get(),
+            EndpointResponse::Multi(multi) => multi.get(hint),
+        }
+    }
+
+    /// Pins the response with the given name or index. Returns `false` when
+    /// the response doesn't exist or the endpoint has single response.
+    pub fn pin(&self, key: &str) -> bool {
+        match self {
+            EndpointResponse::Single(_) => false,
+            EndpointResponse::Multi(multi) => multi.pin(key),
+        }
+    }
+
+    /// Gets all the responses of the endpoint.
+    pub fn responses(&self) -> impl Iterator<Item = &Response> {
+        match self {
+            EndpointResponse::Single(res) => {
+                std::slice::from_ref(res.as_ref())
+            }
+            EndpointResponse::Multi(multi) => multi.responses.as_slice(),
+        }
+        .iter()
+    }
+
+    /// Gets all the responses of the endpoint.
+    pub fn responses_mut(&mut self) -> impl Iterator<Item = &mut Response> {
+        match self {
+            EndpointResponse::Single(res) => {
+                std::slice::from_mut(res.as_mut()).iter_mut()
+            }
+            EndpointResponse::Multi(multi) => multi.responses.iter_mut(),
+        }
+    }
+
+    /// Removes the pinned response.
+    pub fn unpin(&self) {
+        if let EndpointResponse::Multi(multi) = self {
+            multi.unpin();
         }
     }
 }
 
 impl Default for EndpointResponse {
     fn default() -> Self {
-        Self::Single(Response::default())
+        Self::Single(Box::default())
     }
 }
@@ -1,5 +1,5 @@
 use std::sync::{
-    Arc,
+    Arc, RwLock,
     atomic::{AtomicUsize, Ordering},
 };
 
@@ -17,15 +17,29 @@ pub struct MultiResponse {
 
     #[serde(skip)]
     pub cycle_id: Arc<AtomicUsize>,
+    #[serde(skip)]
+    pub pinned: Arc<RwLock<Option<usize>>>,
 }
 
 impl MultiResponse {
-    /// Gets teh endpoint response based on the set strategy.
-    pub fn get(&self) -> &Response {
+    /// Gets the endpoint response.
+    ///
+    /// The response selected by the `hint` takes precedence, followed by the
+    /// pinned response. Otherwise the response is picked based on the set
+    /// strategy.
+    pub fn get(&self, hint: Option<&str>) -> &Response {
         if self.responses.is_empty() {
             panic!("MultiResponse contains no responses!");
         }
 
+        if let Some(id) = hint.and_then(|h| self.find(h)) {
+            return &self.responses[id];
+        }
+
+        if let Some(id) = *self.pinned.read().unwrap() {
+            return &self.responses[id];
+        }
+
         match self.strategy {
             Strategy::Random => {
                 let mut rng = rand::rng();
@@ -39,4 +53,32 @@ impl MultiResponse {
             }
         }
     }
+
+    /// Finds the index of the response with the given name. When no response
+    /// has such name, the key is treated as a zero-based index.
+    pub fn find(&self, key: &str) -> Option<usize> {
+        self.responses
+            .iter()
+            .position(|r| r.name.as_deref() == Some(key))
+            .or_else(|| {
+                key.parse::<usize>()
+                    .ok()
+                    .filter(|id| *id < self.responses.len())
+            })
+    }
+
+    /// Pins the response with the given name or index, so it's always
+    /// returned until reset. Returns `false` when no such response exists.
+    pub fn pin(&self, key: &str) -> bool {
+        let Some(id) = self.find(key) else {
+            return false;
+        };
+        *self.pinned.write().unwrap() = Some(id);
+        true
+    }
+
+    /// Removes the pinned response, returning to the set strategy.
+    pub fn unpin(&self) {
+        *self.pinned.write().unwrap() = None;
+    }
 }
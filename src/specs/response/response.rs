@@ -1,42 +1,133 @@
 use std::collections::HashMap;
 
-use http_body_util::Full;
-use hyper::body::Bytes;
+use hyper::{
+    header::{CACHE_CONTROL, CONTENT_TYPE, HeaderName, HeaderValue},
+    http::response::Builder,
+};
+use indexmap::IndexMap;
+use log::warn;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::Result,
-    server::{HyperRes, url::var::UrlVar},
-    specs::{body::body::Body, status_code::StatusCode},
+    server::{HyperRes, full_body, url::var::UrlVar},
+    specs::{
+        body::body::Body,
+        delay::Delay,
+        response::{Events, Fault},
+        status_code::StatusCode,
+    },
 };
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     #[serde(default)]
     pub status: StatusCode,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub delay: Option<u64>,
+    pub delay: Option<Delay>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<Fault>,
+    /// Additional response headers, their values can contain variables.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub headers: IndexMap<String, Body>,
     #[serde(default, skip_serializing_if = "Body::is_null")]
     pub body: Body,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<Events>,
 }
 
 impl Response {
+    /// Checks the response settings that can't be checked when parsing.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(delay) = &self.delay {
+            delay.validate()?;
+        }
+        if let Some(fault) = &self.fault {
+            fault.validate()?;
+        }
+        if let Some(events) = &self.events {
+            events.validate()?;
+        }
+        Ok(())
+    }
+
     pub fn to_http_response(
         &self,
         vars: &HashMap<String, UrlVar>,
         templates: &HashMap<String, Body>,
     ) -> Result<HyperRes> {
-        let body = self.expand_vars(&vars, templates);
-        let body = serde_json::to_string(&body).unwrap_or("".into());
+        if let Some(events) = &self.events {
+            let mut builder = self.http_builder(vars, templates);
+            if let Some(headers) = builder.headers_mut() {
+                headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("text/event-stream"),
+                );
+                headers.insert(
+                    CACHE_CONTROL,
+                    HeaderValue::from_static("no-cache"),
+                );
+            }
+            return builder
+                .body(events.stream(vars, templates))
+                .map_err(Into::into);
+        }
+
+        let body = self.render_body(vars, templates);
+        if let Some(fault) = &self.fault
+            && fault.triggers()
+        {
+            return fault.apply(self, body, vars, templates);
+        }
 
-        hyper::Response::builder()
-            .status(self.status.0)
-            .header("content-type", "application/json")
-            .body(Full::new(Bytes::from(body)))
+        self.http_builder(vars, templates)
+            .body(full_body(body))
             .map_err(Into::into)
     }
 
+    /// Creates HTTP response builder with the response status and headers.
+    pub fn http_builder(
+        &self,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> Builder {
+        let mut builder = hyper::Response::builder().status(self.status.0);
+        let Some(headers) = builder.headers_mut() else {
+            return builder;
+        };
+
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        for (name, value) in self.headers.iter() {
+            let value = match value.resolve(vars, templates) {
+                serde_yaml::Value::String(s) => s,
+                value => serde_json::to_string(&value).unwrap_or_default(),
+            };
+            match (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => warn!("Invalid response header `{name}`."),
+            }
+        }
+        builder
+    }
+
+    /// Resolves the response body and serializes it to JSON.
+    pub fn render_body(
+        &self,
+        vars: &HashMap<String, UrlVar>,
+        templates: &HashMap<String, Body>,
+    ) -> String {
+        let body = self.expand_vars(vars, templates);
+        serde_json::to_string(&body).unwrap_or("".into())
+    }
+
     pub fn expand_vars(
         &self,
         vars: &HashMap<String, UrlVar>,
@@ -0,0 +1,281 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    error::{Error, Result},
+    specs::{mock_config::MockConfig, mount::Mount},
+};
+
+/// Extensions of the specification files.
+const SPEC_EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+/// Source of the specification files.
+#[derive(Debug, Clone)]
+pub enum Source {
+    File(PathBuf),
+    /// All the specification files directly in the directory.
+    Dir(PathBuf),
+    Glob(glob::Pattern),
+}
+
+/// Specifications merged from multiple sources.
+#[derive(Debug, Default)]
+pub struct LoadedSpecs {
+    pub config: MockConfig,
+    /// All the loaded specification files.
+    pub files: Vec<PathBuf>,
+    /// Sources of the files, including the ones from the `include` lists.
+    pub sources: Vec<Source>,
+    /// Loaded files with the prefix they are mounted at.
+    mounted: HashSet<(PathBuf, String)>,
+    /// Files whose includes are being loaded.
+    loading: Vec<PathBuf>,
+}
+
+impl Source {
+    /// Creates source from the given path. Paths containing `*`, `?` or `[`
+    /// are glob patterns.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = std::path::absolute(path)?;
+        let pattern = path.to_string_lossy();
+        if pattern.contains(['*', '?', '[']) {
+            let pattern = glob::Pattern::new(&pattern).map_err(|e| {
+                Error::Msg(format!("Invalid glob pattern '{pattern}': {e}"))
+            })?;
+            Ok(Source::Glob(pattern))
+        } else if path.is_dir() {
+            Ok(Source::Dir(path))
+        } else {
+            Ok(Source::File(path))
+        }
+    }
+
+    /// Checks whether the given file belongs to the source.
+    pub fn contains(&self, file: &Path) -> bool {
+        match self {
+            Source::File(path) => path == file,
+            Source::Dir(dir) => {
+                file.parent() == Some(dir) && is_spec_file(file)
+            }
+            Source::Glob(pattern) => pattern.matches_path(file),
+        }
+    }
+
+    /// Gets the directory containing the source files.
+    pub fn dir(&self) -> PathBuf {
+        match self {
+            Source::File(path) => {
+                path.parent().unwrap_or(Path::new(".")).to_owned()
+            }
+            Source::Dir(dir) => dir.clone(),
+            Source::Glob(pattern) => Path::new(pattern.as_str())
+                .ancestors()
+                .find(|p| !p.to_string_lossy().contains(['*', '?', '[']))
+                .unwrap_or(Path::new("."))
+                .to_owned(),
+        }
+    }
+
+    /// Lists the specification files of the source in alphabetical order.
+    fn files(&self) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<_> = match self {
+            Source::File(path) if path.is_file() => {
+                return Ok(vec![path.clone()]);
+            }
+            Source::File(_) => vec![],
+            Source::Dir(dir) => fs::read_dir(dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && is_spec_file(p))
+                .collect(),
+            Source::Glob(pattern) => glob::glob(pattern.as_str())
+                .map_err(|e| Error::Msg(e.to_string()))?
+                .filter_map(|p| p.ok())
+                .filter(|p| p.is_file())
+                .collect(),
+        };
+        if files.is_empty() {
+            return Err(Error::Msg(format!(
+                "No specification files found in '{self}'"
+            )));
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) | Source::Dir(path) => {
+                write!(f, "{}", path.display())
+            }
+            Source::Glob(pattern) => write!(f, "{pattern}"),
+        }
+    }
+}
+
+impl LoadedSpecs {
+    /// Loads the specifications from all the sources and merges them.
+    /// Templates, routes and global settings can be defined only once. The
+    /// selected `profile` must be defined in at least one of the files.
+    pub fn load(sources: &[Source], profile: Option<&str>) -> Result<Self> {
+        let mut loaded = Self::default();
+        let mut origins = HashMap::new();
+        for source in sources {
+            let source = source.clone();
+            loaded.load_source(source, None, profile, &mut origins)?;
+        }
+
+        if let Some(profile) = profile
+            && !loaded.config.profiles.contains_key(profile)
+        {
+            return Err(Error::Msg(format!(
+                "Profile '{profile}' is not defined in any specification file"
+            )));
+        }
+        Ok(loaded)
+    }
+
+    /// Loads the files of the source. The `mount` is the mount of the file
+    /// including the source.
+    fn load_source(
+        &mut self,
+        source: Source,
+        mount: Option<Arc<Mount>>,
+        profile: Option<&str>,
+        origins: &mut HashMap<String, PathBuf>,
+    ) -> Result<()> {
+        let files = source.files()?;
+        self.sources.push(source);
+        for file in files {
+            let mut config =
+                MockConfig::load(&file, profile).map_err(|e| {
+                    Error::Msg(format!("Loading '{}': {e}", file.display()))
+                })?;
+            let mount = match config.mount.take() {
+                Some(own) => Some(Arc::new(nest(own, mount.as_deref())?)),
+                None => mount.clone(),
+            };
+
+            // The same file can be matched by multiple sources, but it can
+            // also be mounted at multiple prefixes.
+            let prefix = mount.as_ref().map(|m| m.prefix.clone());
+            if !self
+                .mounted
+                .insert((file.clone(), prefix.unwrap_or_default()))
+            {
+                continue;
+            }
+            if self.loading.contains(&file) {
+                return Err(Error::Msg(format!(
+                    "'{}' includes itself under another mount",
+                    file.display()
+                )));
+            }
+            let remounted = self.files.contains(&file);
+            if !remounted {
+                self.files.push(file.clone());
+            }
+
+            for spec in &mut config.specs {
+                spec.mount = mount.clone();
+            }
+
+            let include = std::mem::take(&mut config.include);
+            self.merge(config, &file, remounted, origins)?;
+
+            self.loading.push(file.clone());
+            let dir = file.parent().unwrap_or(Path::new("."));
+            for include in include {
+                let (path, own) = include.into_parts();
+                let mount = match own {
+                    Some(own) => Some(Arc::new(nest(own, mount.as_deref())?)),
+                    None => mount.clone(),
+                };
+                let source = Source::new(dir.join(path))?;
+                self.load_source(source, mount, profile, origins)?;
+            }
+            self.loading.pop();
+        }
+        Ok(())
+    }
+
+    /// Merges the config loaded from the `file` to the already loaded
+    /// config. Only the routes are merged from the `remounted` file, which
+    /// was already loaded at another prefix.
+    fn merge(
+        &mut self,
+        config: MockConfig,
+        file: &Path,
+        remounted: bool,
+        origins: &mut HashMap<String, PathBuf>,
+    ) -> Result<()> {
+        let mut claim = |what: String| match origins.get(&what) {
+            Some(prev) if prev == file => Err(Error::Msg(format!(
+                "{what} is defined multiple times in '{}'",
+                file.display()
+            ))),
+            Some(prev) => Err(Error::Msg(format!(
+                "{what} is defined in both '{}' and '{}'",
+                prev.display(),
+                file.display()
+            ))),
+            None => {
+                origins.insert(what, file.to_owned());
+                Ok(())
+            }
+        };
+
+        for spec in &config.specs {
+            claim(format!("Route {}", spec.route_key()?))?;
+        }
+        if remounted {
+            self.config.specs.extend(config.specs);
+            return Ok(());
+        }
+        for name in config.templates.keys() {
+            claim(format!("Template '{name}'"))?;
+        }
+        for fallback in &config.fallbacks {
+            claim(format!("Fallback '{}'", fallback.prefix))?;
+        }
+
+        let merged = &mut self.config;
+        macro_rules! merge_opt {
+            ($($field:ident),*) => {$(
+                if let Some(value) = config.$field {
+                    claim(format!("'{}'", stringify!($field)))?;
+                    merged.$field = Some(value);
+                }
+            )*};
+        }
+        merge_opt!(rate_limit, auth, oidc, cors, on_invalid, not_found);
+
+        merged.specs.extend(config.specs);
+        merged.templates.extend(config.templates);
+        merged.fallbacks.extend(config.fallbacks);
+        merged.profiles.extend(config.profiles);
+        Ok(())
+    }
+}
+
+/// Nests the validated mount under the `outer` mount.
+fn nest(mount: Mount, outer: Option<&Mount>) -> Result<Mount> {
+    mount.validate()?;
+    Ok(match outer {
+        Some(outer) => mount.nest(outer),
+        None => mount,
+    })
+}
+
+/// Checks whether the file has extension of the specification file.
+fn is_spec_file(file: &Path) -> bool {
+    file.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SPEC_EXTENSIONS.contains(&e))
+}
@@ -1,25 +1,138 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::{Error, Result},
-    specs::{body::body::Body, method::Method, response::EndpointResponse},
+    server::url::{parser::UrlParser, token::UrlToken},
+    specs::{
+        auth::Auth,
+        graphql::GraphQl,
+        grpc::Grpc,
+        method::Method,
+        mount::Mount,
+        rate_limit::RateLimit,
+        request::{Request, deserialize_request, serialize_request},
+        response::{EndpointResponse, Response},
+        websocket::WebSocket,
+    },
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Spec {
     pub method: Method,
     pub url: String,
-    #[serde(default)]
-    pub request: Option<Body>,
+    /// Expected request body, query parameters, headers and cookies.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_request",
+        serialize_with = "serialize_request",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub request: Option<Request>,
     #[serde(default)]
     pub response: EndpointResponse,
+    /// Response to the request failing the validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_invalid: Option<Response>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<WebSocket>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<GraphQl>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<Grpc>,
+    /// Mount of the specification file containing the endpoint.
+    #[serde(skip)]
+    pub mount: Option<Arc<Mount>>,
 }
 
 impl Spec {
+    /// Gets the method and mounted URL of the endpoint with the variable
+    /// names omitted, so that the routes matching the same requests have the
+    /// same key.
+    pub fn route_key(&self) -> Result<String> {
+        let url = self.mounted_url();
+        let mut chars = url.chars();
+        let mut parser = UrlParser::new(&mut chars);
+        _ = parser.next()?;
+
+        let mut key = hyper::Method::from(self.method.clone()).to_string();
+        key.push(' ');
+        while let Some(segment) = parser.next()? {
+            key.push('/');
+            for token in segment.tokens {
+                match token {
+                    UrlToken::Static(s) => key.push_str(&s),
+                    UrlToken::Var { ty, .. } => {
+                        key.push_str(&format!("{{{ty}}}"))
+                    }
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    /// Gets the serialized spec including its mount, used to detect changes
+    /// of the endpoint.
+    pub fn fingerprint(&self) -> Option<String> {
+        let spec = serde_yaml::to_string(self).ok()?;
+        let mount = serde_yaml::to_string(&self.mount.as_deref()).ok()?;
+        Some(spec + &mount)
+    }
+
+    /// Gets the URL of the endpoint including the mount prefix.
+    pub fn mounted_url(&self) -> String {
+        match &self.mount {
+            Some(mount) => mount.url(&self.url),
+            None => self.url.clone(),
+        }
+    }
+
     pub fn validate(&self) -> Result<()> {
+        if self.websocket.is_some() && !matches!(self.method, Method::Get) {
+            return Err(Error::Msg(format!(
+                "The WebSocket endpoint '{}' must use the 'Get' method",
+                self.url
+            )));
+        }
+        if let Some(ws) = &self.websocket {
+            ws.validate().map_err(|e| {
+                Error::Msg(format!(
+                    "Invalid WebSocket endpoint '{}': {e}",
+                    self.url
+                ))
+            })?;
+        }
+        if self.graphql.is_some()
+            && !matches!(self.method, Method::Get | Method::Post)
+        {
+            return Err(Error::Msg(format!(
+                "The GraphQL endpoint '{}' must use the 'Get' or 'Post' method",
+                self.url
+            )));
+        }
+        if self.grpc.is_some() && !matches!(self.method, Method::Post) {
+            return Err(Error::Msg(format!(
+                "The gRPC endpoint '{}' must use the 'Post' method",
+                self.url
+            )));
+        }
+        for res in self.response.responses().chain(&self.on_invalid) {
+            res.validate().map_err(|e| {
+                Error::Msg(format!(
+                    "Invalid response of the endpoint '{}': {e}",
+                    self.url
+                ))
+            })?;
+        }
+        let has_body = self.request.as_ref().is_some_and(|r| r.body.is_some());
         match &self.method {
             Method::Post | Method::Put | Method::Patch => Ok(()),
-            m if self.request.is_some() => Err(Error::Msg(format!(
+            m if has_body => Err(Error::Msg(format!(
                 "The method '{:?}' cannot have a request body validator",
                 m
             ))),
@@ -0,0 +1,96 @@
+use serde::Serialize;
+
+/// Single failure of the request validation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    /// JSON path of the failing value (e.g. `$.items[0].name`).
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Creates new validation error of the value on the given path.
+    pub fn new(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_owned(),
+            expected: None,
+            actual: None,
+            message: message.into(),
+        }
+    }
+
+    /// Sets the expected and actual value description.
+    pub fn expected(
+        mut self,
+        expected: impl Into<String>,
+        actual: impl Into<String>,
+    ) -> Self {
+        self.expected = Some(expected.into());
+        self.actual = Some(actual.into());
+        self
+    }
+
+    /// Creates error of the value having unexpected type.
+    pub fn type_mismatch(
+        path: &str,
+        expected: &str,
+        actual: &serde_yaml::Value,
+    ) -> Self {
+        let actual = type_name(actual);
+        Self::new(path, format!("expected {expected}, found {actual}"))
+            .expected(expected, actual)
+    }
+
+    /// Creates error of the value not equal to the expected value.
+    pub fn value_mismatch(
+        path: &str,
+        expected: &serde_yaml::Value,
+        actual: &serde_yaml::Value,
+    ) -> Self {
+        Self::new(path, "unexpected value")
+            .expected(describe(expected), describe(actual))
+    }
+}
+
+/// Gets the JSON type name of the value.
+pub fn type_name(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "boolean",
+        serde_yaml::Value::Number(_) => "number",
+        serde_yaml::Value::String(_) => "string",
+        serde_yaml::Value::Sequence(_) => "array",
+        serde_yaml::Value::Mapping(_) => "object",
+        serde_yaml::Value::Tagged(tagged) => type_name(&tagged.value),
+    }
+}
+
+/// Appends the object key to the JSON path.
+pub fn key_path(path: &str, key: &serde_yaml::Value) -> String {
+    match key.as_str() {
+        Some(key)
+            if !key.is_empty()
+                && key.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            format!("{path}.{key}")
+        }
+        _ => {
+            let key = serde_json::to_string(key).unwrap_or_default();
+            format!("{path}[{key}]")
+        }
+    }
+}
+
+/// Appends the array index to the JSON path.
+pub fn index_path(path: &str, index: usize) -> String {
+    format!("{path}[{index}]")
+}
+
+/// Describes the value for the validation error.
+pub fn describe(value: &serde_yaml::Value) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
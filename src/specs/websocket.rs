@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    server::url::var::UrlVar,
+    specs::{body::body::Body, pattern::Pattern},
+};
+
+/// WebSocket endpoint specification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebSocket {
+    /// Messages sent by the server on connect or on timer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<WsMessage>,
+    /// Replies to the incoming messages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<WsReply>,
+}
+
+/// Message sent by the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WsMessage {
+    /// Delay in milliseconds after connecting before sending the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<u64>,
+    /// Interval in milliseconds in which the message is repeatedly sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<u64>,
+    pub body: Body,
+}
+
+/// Reply to the incoming messages matching the pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WsReply {
+    #[serde(rename = "match")]
+    pub pattern: Pattern,
+    pub body: Body,
+}
+
+impl WebSocket {
+    /// Checks that the repeated messages have non zero interval.
+    pub fn validate(&self) -> Result<()> {
+        if self.messages.iter().any(|m| m.every == Some(0)) {
+            return Err(Error::Msg(
+                "The message interval 'every' must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Finds reply to the incoming message. On success, adds the `message`
+    /// variable and named groups of the matched pattern to the `vars`.
+    pub fn reply(
+        &self,
+        msg: &str,
+        vars: &mut HashMap<String, UrlVar>,
+    ) -> Option<&Body> {
+        self.replies.iter().find_map(|reply| {
+            let captures = reply.pattern.0.captures(msg)?;
+            for name in reply.pattern.0.capture_names().flatten() {
+                if let Some(value) = captures.name(name) {
+                    vars.insert(
+                        name.to_owned(),
+                        UrlVar::String(value.as_str().to_owned()),
+                    );
+                }
+            }
+            vars.insert("message".into(), UrlVar::String(msg.to_owned()));
+            Some(&reply.body)
+        })
+    }
+}